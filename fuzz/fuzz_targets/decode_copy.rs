@@ -0,0 +1,12 @@
+//! Decode arbitrary data using the Copy method.
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use sevenz::read::CodecRegistry;
+
+fuzz_target!(|data: &[u8]| {
+    let codec = CodecRegistry::new().decoder(&[0x00], None).unwrap();
+    if let Ok(decoded) = codec.decode(data) {
+        assert_eq!(decoded, data);
+    }
+});
@@ -0,0 +1,21 @@
+//! Decode arbitrary data using the LZMA2 method.
+//!
+//! The first byte is used as the coder's properties, the rest as the data.
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use sevenz::read::CodecRegistry;
+
+fuzz_target!(|data: &[u8]| {
+    let (props, data) = match data.split_first() {
+        Some((props, data)) => (core::slice::from_ref(props), data),
+        None => return,
+    };
+    let registry = CodecRegistry::new();
+    if let Ok(codec) = registry.decoder(&[0x21], Some(props)) {
+        let _ = codec.decode(data);
+        if let Ok(decoded) = codec.decode_bounded(data, 1024) {
+            assert!(decoded.len() <= 1024);
+        }
+    }
+});
@@ -0,0 +1,13 @@
+//! Parse arbitrary data as an archive and extract everything in it.
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(files) = sevenz::read::list_files(data) {
+        for file in files {
+            let _ = file.extract_contents_vec();
+            let _ = file.symlink_target();
+        }
+    }
+});
@@ -0,0 +1,11 @@
+//! Match arbitrary glob patterns against arbitrary names, separated by a NUL byte.
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let mut parts = data.splitn(2, |b| *b == 0);
+    let pattern = String::from_utf8_lossy(parts.next().unwrap_or_default());
+    let name = String::from_utf8_lossy(parts.next().unwrap_or_default());
+    let _ = sevenz::read::glob_match(&pattern, &name);
+});
@@ -0,0 +1,24 @@
+//! Parse arbitrary data as an archive, without decoding any contents.
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let _ = sevenz::read::archive_info(data);
+    let recover = sevenz::read::ReadOptions {
+        recover: true,
+        ..Default::default()
+    };
+    let _ = sevenz::read::archive_info_with_options(data, &recover);
+    if let Ok(files) = sevenz::read::list_files(data) {
+        for file in files {
+            let _ = file.name();
+            let _ = file.comment();
+            let _ = file.kind();
+            let _ = file.attributes();
+            let _ = file.modified();
+            let _ = file.packed_size();
+            let _ = file.method();
+        }
+    }
+});
@@ -0,0 +1,13 @@
+//! Parse arbitrary data as an archive and check the integrity of everything in it.
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let _ = sevenz::read::verify(data);
+    let recover = sevenz::read::ReadOptions {
+        recover: true,
+        ..Default::default()
+    };
+    let _ = sevenz::read::verify_with_options(data, &recover);
+});
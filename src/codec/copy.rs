@@ -1,5 +1,6 @@
-use super::Codec;
+use super::{Codec, CodecError, CodecFactory, Encoder};
 
+use alloc::boxed::Box;
 use alloc::vec::Vec;
 
 /// The trivial codec.
@@ -15,7 +16,23 @@ impl Copy {
 }
 
 impl Codec for Copy {
-    fn decode(&self, data: &[u8]) -> Vec<u8> {
-        return Vec::from(data);
+    fn decode(&self, data: &[u8]) -> Result<Vec<u8>, CodecError> {
+        return Ok(Vec::from(data));
+    }
+}
+
+impl Encoder for Copy {
+    fn encode(&self, data: &[u8]) -> Result<Vec<u8>, CodecError> {
+        return Ok(Vec::from(data));
+    }
+}
+
+impl CodecFactory for Copy {
+    fn decoder(&self, _props: Option<&[u8]>) -> Result<Box<dyn Codec>, CodecError> {
+        return Ok(Box::new(Copy::new()));
+    }
+
+    fn encoder(&self) -> Option<Box<dyn Encoder>> {
+        return Some(Box::new(Copy::new()));
     }
 }
@@ -0,0 +1,59 @@
+use super::{Codec, CodecError, CodecFactory};
+
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+
+/// The LZMA2 codec.
+///
+/// LZMA2 is a container format that splits data into chunks,
+/// each of which is either stored uncompressed or compressed using LZMA.
+/// Only uncompressed chunks are currently supported.
+pub struct Lzma2 {}
+
+impl Lzma2 {
+    /// Creates a new `Lzma2` codec.
+    pub fn new() -> Lzma2 {
+        return Lzma2 {};
+    }
+}
+
+impl Codec for Lzma2 {
+    fn decode(&self, data: &[u8]) -> Result<Vec<u8>, CodecError> {
+        return self.decode_bounded(data, usize::MAX);
+    }
+
+    fn decode_bounded(&self, data: &[u8], max_size: usize) -> Result<Vec<u8>, CodecError> {
+        let mut res = Vec::new();
+        let mut pos = 0;
+        loop {
+            let control = *data.get(pos).ok_or(CodecError::InvalidData)?;
+            pos += 1;
+            match control {
+                // End of data
+                0x00 => return Ok(res),
+                // Uncompressed chunk, with or without dictionary reset
+                0x01 | 0x02 => {
+                    let size = match data.get(pos..pos + 2) {
+                        Some(s) => ((s[0] as usize) << 8 | s[1] as usize) + 1,
+                        None => return Err(CodecError::InvalidData),
+                    };
+                    pos += 2;
+                    if res.len() + size > max_size {
+                        return Err(CodecError::InvalidData);
+                    }
+                    let chunk = data.get(pos..pos + size).ok_or(CodecError::InvalidData)?;
+                    res.extend_from_slice(chunk);
+                    pos += size;
+                }
+                0x80..=0xFF => return Err(CodecError::Unsupported("LZMA-compressed LZMA2 chunks")),
+                _ => return Err(CodecError::InvalidData),
+            }
+        }
+    }
+}
+
+impl CodecFactory for Lzma2 {
+    fn decoder(&self, _props: Option<&[u8]>) -> Result<Box<dyn Codec>, CodecError> {
+        return Ok(Box::new(Lzma2::new()));
+    }
+}
@@ -0,0 +1,527 @@
+//! This module implements describing the methods listed in 7zip's methods.txt,
+//! based on the coder IDs and properties stored in the archive.
+
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::convert::TryInto;
+use core::fmt;
+
+/// Properties of the LZMA method.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LzmaProperties {
+    /// Number of literal context bits.
+    pub lc: u8,
+    /// Number of literal position bits.
+    pub lp: u8,
+    /// Number of position bits.
+    pub pb: u8,
+    pub dict_size: u32,
+}
+
+impl LzmaProperties {
+    /// Parse the 5 property bytes.
+    pub fn parse(props: &[u8]) -> Option<LzmaProperties> {
+        let d = *props.first()?;
+        if d >= 9 * 5 * 5 {
+            return None;
+        }
+        let dict_size = u32::from_le_bytes(props.get(1..5)?.try_into().ok()?);
+        return Some(LzmaProperties {
+            lc: d % 9,
+            lp: (d / 9) % 5,
+            pb: d / 45,
+            dict_size,
+        });
+    }
+}
+
+/// Properties of the LZMA2 method.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Lzma2Properties {
+    pub dict_size: u32,
+}
+
+impl Lzma2Properties {
+    /// Parse the single property byte, which encodes the dictionary size.
+    pub fn parse(props: &[u8]) -> Option<Lzma2Properties> {
+        let b = *props.first()? as u32;
+        let dict_size = match b {
+            0..=39 => (2 | (b & 1)) << (b / 2 + 11),
+            40 => u32::MAX,
+            _ => return None,
+        };
+        return Some(Lzma2Properties { dict_size });
+    }
+}
+
+/// Properties of the PPMd method.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PpmdProperties {
+    /// Model order.
+    pub order: u8,
+    /// Memory size in bytes.
+    pub mem_size: u32,
+}
+
+impl PpmdProperties {
+    pub fn parse(props: &[u8]) -> Option<PpmdProperties> {
+        let order = *props.first()?;
+        let mem_size = u32::from_le_bytes(props.get(1..5)?.try_into().ok()?);
+        return Some(PpmdProperties { order, mem_size });
+    }
+}
+
+/// Properties of the Delta filter.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DeltaProperties {
+    /// Distance in bytes, between 1 and 256.
+    pub distance: u16,
+}
+
+impl DeltaProperties {
+    pub fn parse(props: &[u8]) -> Option<DeltaProperties> {
+        return Some(DeltaProperties {
+            distance: *props.first()? as u16 + 1,
+        });
+    }
+}
+
+/// Properties of 7zip's AES-256 + SHA-256 encryption.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AesProperties {
+    /// The key is derived using 2^`num_cycles_power` rounds of SHA-256.
+    pub num_cycles_power: u8,
+    pub salt: Vec<u8>,
+    pub iv: Vec<u8>,
+}
+
+impl AesProperties {
+    pub fn parse(props: &[u8]) -> Option<AesProperties> {
+        let b0 = *props.first()?;
+        let num_cycles_power = b0 & 0x3F;
+        if b0 & 0xC0 == 0 {
+            return Some(AesProperties {
+                num_cycles_power,
+                salt: Vec::new(),
+                iv: Vec::new(),
+            });
+        }
+        let b1 = *props.get(1)?;
+        let salt_size = (((b0 >> 7) & 1) + (b1 >> 4)) as usize;
+        let iv_size = (((b0 >> 6) & 1) + (b1 & 0x0F)) as usize;
+        let salt = props.get(2..2 + salt_size)?;
+        let iv = props.get(2 + salt_size..2 + salt_size + iv_size)?;
+        return Some(AesProperties {
+            num_cycles_power,
+            salt: Vec::from(salt),
+            iv: Vec::from(iv),
+        });
+    }
+}
+
+/// A method, as listed in 7zip's methods.txt.
+///
+/// Methods with properties carry them parsed, or `None` if they're missing or malformed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Method {
+    Copy,
+    Delta(Option<DeltaProperties>),
+    /// The x86 branch converter, also known as BCJ.
+    X86,
+    Bcj2,
+    Ppc,
+    Ia64,
+    Arm,
+    Armt,
+    Arm64,
+    RiscV,
+    Sparc,
+    Alpha,
+    M68,
+    Swap2,
+    Swap4,
+    Lzma(Option<LzmaProperties>),
+    Lzma2(Option<Lzma2Properties>),
+    Ppmd(Option<PpmdProperties>),
+    Shrink,
+    Implode,
+    Deflate,
+    Deflate64,
+    BZip2,
+    /// LZMA as used by the Zip format.
+    ZipLzma,
+    Zstd,
+    Xz,
+    Jpeg,
+    WavPack,
+    /// PPMd as used by the Zip format.
+    ZipPpmd,
+    WzAes,
+    Rar1,
+    Rar2,
+    Rar3,
+    Rar5,
+    Arj,
+    Arj4,
+    /// Unix compress, also known as LZW.
+    Z,
+    Lzh,
+    /// Quantum as used by the Cab format.
+    Quantum,
+    /// LZX as used by the Cab format.
+    Lzx,
+    DeflateNsis,
+    BZip2Nsis,
+    ZipCrypto,
+    Rar29Aes,
+    /// AES-256 with a key derived using SHA-256, the encryption used by 7z archives.
+    Aes(Option<AesProperties>),
+    /// AES without key derivation, by the last byte of it's ID, which encodes the key size and mode.
+    RawAes(u8),
+    Lzham,
+    Brotli,
+    Lz4,
+    Lz5,
+    Lizard,
+    /// A codec not included in 7zip, by the last two bytes of it's ID.
+    External([u8; 2]),
+    /// A method not listed in methods.txt, by its ID.
+    Unknown(Vec<u8>),
+}
+
+impl Method {
+    /// Describe the method with the given ID and properties.
+    pub fn from_coder(id: &[u8], props: Option<&[u8]>) -> Method {
+        let props = props.unwrap_or(&[]);
+        return match id {
+            [0x00] => Method::Copy,
+            [0x03] => Method::Delta(DeltaProperties::parse(props)),
+            [0x04] | [0x03, 0x03, 0x01, 0x03] => Method::X86,
+            [0x05] | [0x03, 0x03, 0x02, 0x05] => Method::Ppc,
+            [0x06] | [0x03, 0x03, 0x04, 0x01] => Method::Ia64,
+            [0x07] | [0x03, 0x03, 0x05, 0x01] => Method::Arm,
+            [0x08] | [0x03, 0x03, 0x07, 0x01] => Method::Armt,
+            [0x09] | [0x03, 0x03, 0x08, 0x05] => Method::Sparc,
+            [0x0A] => Method::Arm64,
+            [0x0B] => Method::RiscV,
+            [0x21] => Method::Lzma2(Lzma2Properties::parse(props)),
+            [0x02, 0x03, 0x02] => Method::Swap2,
+            [0x02, 0x03, 0x04] => Method::Swap4,
+            [0x03, 0x01, 0x01] => Method::Lzma(LzmaProperties::parse(props)),
+            [0x03, 0x03, 0x01, 0x1B] => Method::Bcj2,
+            [0x03, 0x03, 0x03, 0x01] => Method::Alpha,
+            [0x03, 0x03, 0x06, 0x05] => Method::M68,
+            [0x03, 0x04, 0x01] => Method::Ppmd(PpmdProperties::parse(props)),
+            [0x04, 0x01, 0x00] => Method::Copy,
+            [0x04, 0x01, 0x01] => Method::Shrink,
+            [0x04, 0x01, 0x06] | [0x04, 0x01, 0x0A] => Method::Implode,
+            [0x04, 0x01, 0x08] => Method::Deflate,
+            [0x04, 0x01, 0x09] => Method::Deflate64,
+            [0x04, 0x01, 0x0C] | [0x04, 0x02, 0x02] => Method::BZip2,
+            [0x04, 0x01, 0x0E] => Method::ZipLzma,
+            [0x04, 0x01, 0x5D] => Method::Zstd,
+            [0x04, 0x01, 0x5F] => Method::Xz,
+            [0x04, 0x01, 0x60] => Method::Jpeg,
+            [0x04, 0x01, 0x61] => Method::WavPack,
+            [0x04, 0x01, 0x62] => Method::ZipPpmd,
+            [0x04, 0x01, 0x63] => Method::WzAes,
+            [0x04, 0x03, 0x01] => Method::Rar1,
+            [0x04, 0x03, 0x02] => Method::Rar2,
+            [0x04, 0x03, 0x03] => Method::Rar3,
+            [0x04, 0x03, 0x05] => Method::Rar5,
+            [0x04, 0x04, 0x01] => Method::Arj,
+            [0x04, 0x04, 0x02] => Method::Arj4,
+            [0x04, 0x05] => Method::Z,
+            [0x04, 0x06] => Method::Lzh,
+            [0x04, 0x08, 0x00] => Method::Copy,
+            [0x04, 0x08, 0x01] => Method::Deflate,
+            [0x04, 0x08, 0x02] => Method::Quantum,
+            [0x04, 0x08, 0x03] => Method::Lzx,
+            [0x04, 0x09, 0x01] => Method::DeflateNsis,
+            [0x04, 0x09, 0x02] => Method::BZip2Nsis,
+            [0x04, 0xF7, 0x10, 0x01] => Method::Lzham,
+            [0x04, 0xF7, 0x11, 0x01] => Method::Zstd,
+            [0x04, 0xF7, 0x11, 0x02] => Method::Brotli,
+            [0x04, 0xF7, 0x11, 0x04] => Method::Lz4,
+            [0x04, 0xF7, 0x11, 0x05] => Method::Lz5,
+            [0x04, 0xF7, 0x11, 0x06] => Method::Lizard,
+            [0x04, 0xF7, a, b] => Method::External([*a, *b]),
+            [0x06, 0xF0, 0x01, mode] => Method::RawAes(*mode),
+            [0x06, 0xF1, 0x01, 0x01] => Method::ZipCrypto,
+            [0x06, 0xF1, 0x03, 0x03] => Method::Rar29Aes,
+            [0x06, 0xF1, 0x07, 0x01] => Method::Aes(AesProperties::parse(props)),
+            _ => Method::Unknown(Vec::from(id)),
+        };
+    }
+
+    /// The name 7zip uses for the method.
+    pub fn name(&self) -> String {
+        let name = match self {
+            Method::Copy => "Copy",
+            Method::Delta(_) => "Delta",
+            Method::X86 => "BCJ",
+            Method::Bcj2 => "BCJ2",
+            Method::Ppc => "PPC",
+            Method::Ia64 => "IA64",
+            Method::Arm => "ARM",
+            Method::Armt => "ARMT",
+            Method::Arm64 => "ARM64",
+            Method::RiscV => "RISCV",
+            Method::Sparc => "SPARC",
+            Method::Alpha => "Alpha",
+            Method::M68 => "M68",
+            Method::Swap2 => "Swap2",
+            Method::Swap4 => "Swap4",
+            Method::Lzma(_) => "LZMA",
+            Method::Lzma2(_) => "LZMA2",
+            Method::Ppmd(_) => "PPMD",
+            Method::Shrink => "Shrink",
+            Method::Implode => "Implode",
+            Method::Deflate => "Deflate",
+            Method::Deflate64 => "Deflate64",
+            Method::BZip2 => "BZip2",
+            Method::ZipLzma => "LZMA-zip",
+            Method::Zstd => "ZSTD",
+            Method::Xz => "xz",
+            Method::Jpeg => "Jpeg",
+            Method::WavPack => "WavPack",
+            Method::ZipPpmd => "PPMd-zip",
+            Method::WzAes => "wzAES",
+            Method::Rar1 => "Rar1",
+            Method::Rar2 => "Rar2",
+            Method::Rar3 => "Rar3",
+            Method::Rar5 => "Rar5",
+            Method::Arj => "Arj",
+            Method::Arj4 => "Arj4",
+            Method::Z => "Z",
+            Method::Lzh => "LZH",
+            Method::Quantum => "Quantum",
+            Method::Lzx => "LZX",
+            Method::DeflateNsis => "DeflateNSIS",
+            Method::BZip2Nsis => "BZip2NSIS",
+            Method::ZipCrypto => "ZipCrypto",
+            Method::Rar29Aes => "Rar29AES",
+            Method::Aes(_) => "7zAES",
+            Method::RawAes(mode) => return raw_aes_name(*mode),
+            Method::Lzham => "LZHAM",
+            Method::Brotli => "BROTLI",
+            Method::Lz4 => "LZ4",
+            Method::Lz5 => "LZ5",
+            Method::Lizard => "LIZARD",
+            Method::External([a, b]) => return alloc::format!("04F7{:02X}{:02X}", a, b),
+            Method::Unknown(id) => return id.iter().map(|b| alloc::format!("{:02X}", b)).collect(),
+        };
+        return String::from(name);
+    }
+
+    /// Whether the method encrypts data.
+    pub fn is_encryption(&self) -> bool {
+        return matches!(
+            self,
+            Method::WzAes
+                | Method::ZipCrypto
+                | Method::Rar29Aes
+                | Method::Aes(_)
+                | Method::RawAes(_)
+        );
+    }
+}
+
+/// Name AES without key derivation like 7zip does, e.g. `AES256CBC`.
+///
+/// The high nibble of `mode` gives the key size, the low one the block cipher mode.
+fn raw_aes_name(mode: u8) -> String {
+    let key_bits = match mode >> 4 {
+        0x0 => "128",
+        0x4 => "192",
+        0x8 => "256",
+        0xC => "",
+        _ => return alloc::format!("06F001{:02X}", mode),
+    };
+    let block_mode = match mode & 0x0F {
+        0 => "ECB",
+        1 => "CBC",
+        2 => "CFB",
+        3 => "OFB",
+        4 => "CTR",
+        _ => return alloc::format!("06F001{:02X}", mode),
+    };
+    return alloc::format!("AES{}{}", key_bits, block_mode);
+}
+
+/// Format a size the way 7zip does in method descriptions.
+///
+/// Powers of 2 are given as their exponent, other sizes in the largest unit dividing them.
+/// The largest LZMA2 dictionary is one byte short of 4 GiB, and given as `4G-1`.
+fn fmt_size(f: &mut fmt::Formatter, size: u32) -> fmt::Result {
+    if size == u32::MAX {
+        return write!(f, "4G-1");
+    }
+    if size.is_power_of_two() {
+        return write!(f, "{}", size.trailing_zeros());
+    }
+    if size & ((1 << 20) - 1) == 0 {
+        return write!(f, "{}m", size >> 20);
+    }
+    if size & ((1 << 10) - 1) == 0 {
+        return write!(f, "{}k", size >> 10);
+    }
+    return write!(f, "{}b", size);
+}
+
+impl fmt::Display for Method {
+    /// Format the method like 7zip lists it, for example `LZMA2:24` or `LZMA:16:lc4`.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name())?;
+        match self {
+            Method::Lzma(Some(p)) => {
+                write!(f, ":")?;
+                fmt_size(f, p.dict_size)?;
+                if p.lc != 3 {
+                    write!(f, ":lc{}", p.lc)?;
+                }
+                if p.lp != 0 {
+                    write!(f, ":lp{}", p.lp)?;
+                }
+                if p.pb != 2 {
+                    write!(f, ":pb{}", p.pb)?;
+                }
+            }
+            Method::Lzma2(Some(p)) => {
+                write!(f, ":")?;
+                fmt_size(f, p.dict_size)?;
+            }
+            Method::Ppmd(Some(p)) => {
+                write!(f, ":o{}:mem", p.order)?;
+                fmt_size(f, p.mem_size)?;
+            }
+            Method::Delta(Some(p)) => write!(f, ":{}", p.distance)?,
+            Method::Aes(Some(p)) => write!(f, ":{}", p.num_cycles_power)?,
+            _ => (),
+        }
+        return Ok(());
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use alloc::string::ToString;
+    use alloc::vec;
+
+    #[test]
+    fn lzma_properties() {
+        // lc=3, lp=0, pb=2, 16 MiB
+        let m = Method::from_coder(&[0x03, 0x01, 0x01], Some(&[0x5D, 0, 0, 0, 1]));
+        assert_eq!(
+            m,
+            Method::Lzma(Some(LzmaProperties {
+                lc: 3,
+                lp: 0,
+                pb: 2,
+                dict_size: 1 << 24
+            }))
+        );
+        assert_eq!(m.to_string(), "LZMA:24");
+
+        // lc=4, lp=0, pb=2, 3 MiB
+        let m = Method::from_coder(&[0x03, 0x01, 0x01], Some(&[0x5E, 0, 0, 0x30, 0]));
+        assert_eq!(m.to_string(), "LZMA:3m:lc4");
+
+        let m = Method::from_coder(&[0x03, 0x01, 0x01], Some(&[0x5D]));
+        assert_eq!(m, Method::Lzma(None));
+        assert_eq!(m.to_string(), "LZMA");
+    }
+
+    #[test]
+    fn lzma2_properties() {
+        assert_eq!(Lzma2Properties::parse(&[0]).unwrap().dict_size, 4 << 10);
+        assert_eq!(Lzma2Properties::parse(&[1]).unwrap().dict_size, 6 << 10);
+        assert_eq!(Lzma2Properties::parse(&[40]).unwrap().dict_size, u32::MAX);
+        assert_eq!(Lzma2Properties::parse(&[41]), None);
+
+        assert_eq!(
+            Method::from_coder(&[0x21], Some(&[24])).to_string(),
+            "LZMA2:24"
+        );
+        assert_eq!(
+            Method::from_coder(&[0x21], Some(&[25])).to_string(),
+            "LZMA2:24m"
+        );
+        assert_eq!(
+            Method::from_coder(&[0x21], Some(&[40])).to_string(),
+            "LZMA2:4G-1"
+        );
+    }
+
+    #[test]
+    fn other_properties() {
+        let m = Method::from_coder(&[0x03, 0x04, 0x01], Some(&[6, 0, 0, 0, 1]));
+        assert_eq!(m.to_string(), "PPMD:o6:mem24");
+        assert_eq!(
+            Method::from_coder(&[0x03], Some(&[3])).to_string(),
+            "Delta:4"
+        );
+        assert_eq!(
+            Method::from_coder(&[0x03, 0x03, 0x01, 0x03], None).to_string(),
+            "BCJ"
+        );
+        assert_eq!(Method::from_coder(&[0x7F, 0x01], None).to_string(), "7F01");
+        assert_eq!(Method::from_coder(&[0x04, 0x05], None), Method::Z);
+        assert_eq!(
+            Method::from_coder(&[0x04, 0x08, 0x01], None),
+            Method::Deflate
+        );
+        assert_eq!(
+            Method::from_coder(&[0x04, 0x08, 0x03], None).to_string(),
+            "LZX"
+        );
+
+        // 19 cycles, 1 byte of salt, 16 bytes of IV
+        let mut props = vec![0x80 | 0x40 | 19, 0x0F, 0xAA];
+        props.extend_from_slice(&[0xBB; 16]);
+        let m = Method::from_coder(&[0x06, 0xF1, 0x07, 0x01], Some(&props));
+        assert_eq!(
+            m,
+            Method::Aes(Some(AesProperties {
+                num_cycles_power: 19,
+                salt: vec![0xAA],
+                iv: vec![0xBB; 16],
+            }))
+        );
+        assert!(m.is_encryption());
+        assert_eq!(m.to_string(), "7zAES:19");
+
+        let m = Method::from_coder(&[0x06, 0xF0, 0x01, 0x81], None);
+        assert_eq!(m, Method::RawAes(0x81));
+        assert!(m.is_encryption());
+        assert_eq!(m.to_string(), "AES256CBC");
+        assert_eq!(
+            Method::from_coder(&[0x06, 0xF0, 0x01, 0xC4], None).to_string(),
+            "AESCTR"
+        );
+        assert_eq!(
+            Method::from_coder(&[0x06, 0xF0, 0x01, 0x0F], None).to_string(),
+            "06F0010F"
+        );
+    }
+
+    #[test]
+    fn external_codecs() {
+        assert_eq!(
+            Method::from_coder(&[0x04, 0xF7, 0x11, 0x01], None),
+            Method::Zstd
+        );
+        assert_eq!(
+            Method::from_coder(&[0x04, 0xF7, 0x11, 0x04], None).to_string(),
+            "LZ4"
+        );
+        let m = Method::from_coder(&[0x04, 0xF7, 0x12, 0x01], None);
+        assert_eq!(m, Method::External([0x12, 0x01]));
+        assert_eq!(m.to_string(), "04F71201");
+        assert_eq!(
+            Method::from_coder(&[0x04, 0xF7, 0x12], None),
+            Method::Unknown(vec![0x04, 0xF7, 0x12])
+        );
+    }
+}
@@ -1,11 +1,17 @@
-//! This module contains decoders (and, eventually, encoders)
+//! This module contains decoders and encoders
 //! for stream formats documented in 7zip's methods.txt.
 
 mod copy;
 pub use copy::*;
+mod lzma2;
+pub use lzma2::*;
+mod method;
+pub use method::*;
+mod registry;
+pub use registry::*;
 
 use alloc::vec::Vec;
-use core::convert::TryFrom;
+use core::fmt;
 
 /// The main interface trait for other code to use.
 ///
@@ -20,31 +26,60 @@ pub trait Codec {
     /// if a coded is determined to require it.
     ///
     /// Also, the simplistic u8 data buffers will probably be swapped out in future to enable streaming.
-    fn decode(&self, data: &[u8]) -> Vec<u8>;
-}
+    fn decode(&self, data: &[u8]) -> Result<Vec<u8>, CodecError>;
 
-/// All currently supported codecs.
-pub enum Codecs {
-    /// As the name implies, simply copies the data byte-for-byte.
-    Copy,
+    /// Like `decode()`, but failing once the decoded data would exceed `max_size` bytes.
+    ///
+    /// The default implementation only checks after decoding everything,
+    /// codecs should override it to stop early if they can.
+    fn decode_bounded(&self, data: &[u8], max_size: usize) -> Result<Vec<u8>, CodecError> {
+        let res = self.decode(data)?;
+        if res.len() > max_size {
+            return Err(CodecError::InvalidData);
+        }
+        return Ok(res);
+    }
 }
 
-impl TryFrom<Vec<u8>> for Codecs {
-    type Error = CodecError;
-    /// Parse a codec ID.
-    /// It may be 1 to 4 bytes long.
-    fn try_from(value: Vec<u8>) -> Result<Self, Self::Error> {
-        // 00 - Copy
-        match value.as_slice() {
-            [0] => return Ok(Codecs::Copy),
-            _ => return Err(CodecError::InvalidCodecID(value)),
-        }
+/// The counterpart to `Codec`, for methods which support encoding.
+pub trait Encoder {
+    /// Take the given data buffer and encode it.
+    fn encode(&self, data: &[u8]) -> Result<Vec<u8>, CodecError>;
+
+    /// The coder properties to store in the archive alongside the encoded data.
+    fn properties(&self) -> Option<Vec<u8>> {
+        return None;
     }
 }
 
 /// The top-level codec error type.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum CodecError {
     /// The archive contained an invalid codec ID.
     InvalidCodecID(Vec<u8>),
+    /// The data uses a feature of the codec which is not yet supported.
+    Unsupported(&'static str),
+    /// The method is known, but decoding it is not yet supported.
+    UnsupportedMethod(Method),
+    /// The data could not be decoded, because it's malformed.
+    InvalidData,
+    /// Decoding would exceed one of the configured limits.
+    LimitExceeded(crate::options::Limit),
 }
+
+impl fmt::Display for CodecError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return match self {
+            CodecError::InvalidCodecID(id) => {
+                write!(f, "invalid codec ID {}", Method::Unknown(id.clone()))
+            }
+            CodecError::Unsupported(feature) => write!(f, "unsupported feature: {}", feature),
+            CodecError::UnsupportedMethod(method) => write!(f, "unsupported method {}", method),
+            CodecError::InvalidData => write!(f, "invalid data"),
+            CodecError::LimitExceeded(limit) => write!(f, "{} limit exceeded", limit),
+        };
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for CodecError {}
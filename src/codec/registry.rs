@@ -0,0 +1,174 @@
+//! This module implements a registry mapping method IDs to codecs,
+//! so users can plug in methods the crate doesn't implement itself.
+
+use super::*;
+
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use core::fmt;
+
+/// Creates codecs for a method.
+///
+/// Any `Fn(Option<&[u8]>) -> Result<Box<dyn Codec>, CodecError>` is a factory for a decoder-only method.
+pub trait CodecFactory: Send + Sync {
+    /// Create a decoder, given the coder's properties as stored in the archive.
+    fn decoder(&self, props: Option<&[u8]>) -> Result<Box<dyn Codec>, CodecError>;
+
+    /// Create an encoder, if the method supports encoding.
+    fn encoder(&self) -> Option<Box<dyn Encoder>> {
+        return None;
+    }
+}
+
+impl<F> CodecFactory for F
+where
+    F: Fn(Option<&[u8]>) -> Result<Box<dyn Codec>, CodecError> + Send + Sync,
+{
+    fn decoder(&self, props: Option<&[u8]>) -> Result<Box<dyn Codec>, CodecError> {
+        return self(props);
+    }
+}
+
+/// The codecs available for each method ID.
+///
+/// `CodecRegistry::new()` has all the codecs built into the crate registered,
+/// use `CodecRegistry::empty()` to start from scratch.
+pub struct CodecRegistry {
+    factories: Vec<(Vec<u8>, Box<dyn CodecFactory>)>,
+}
+
+impl CodecRegistry {
+    /// Creates a registry containing the built-in codecs.
+    pub fn new() -> CodecRegistry {
+        let mut registry = CodecRegistry::empty();
+        registry.register(&[0x00], Copy::new());
+        registry.register(&[0x21], Lzma2::new());
+        return registry;
+    }
+
+    /// Creates a registry without any codecs.
+    pub fn empty() -> CodecRegistry {
+        return CodecRegistry {
+            factories: Vec::new(),
+        };
+    }
+
+    /// Register the factory for the method with the given ID, replacing any previously registered one.
+    pub fn register<F: CodecFactory + 'static>(&mut self, id: &[u8], factory: F) -> &mut Self {
+        self.factories.retain(|(i, _)| i != id);
+        self.factories.push((Vec::from(id), Box::new(factory)));
+        return self;
+    }
+
+    /// Get the factory registered for the method with the given ID.
+    pub fn get(&self, id: &[u8]) -> Option<&dyn CodecFactory> {
+        return self
+            .factories
+            .iter()
+            .find(|(i, _)| i == id)
+            .map(|(_, f)| f.as_ref());
+    }
+
+    /// Create a decoder for the method with the given ID and properties.
+    pub fn decoder(&self, id: &[u8], props: Option<&[u8]>) -> Result<Box<dyn Codec>, CodecError> {
+        return match self.get(id) {
+            Some(factory) => factory.decoder(props),
+            None => Err(unsupported(id, props)),
+        };
+    }
+
+    /// Create an encoder for the method with the given ID.
+    pub fn encoder(&self, id: &[u8]) -> Result<Box<dyn Encoder>, CodecError> {
+        return match self.get(id).and_then(|f| f.encoder()) {
+            Some(encoder) => Ok(encoder),
+            None => Err(unsupported(id, None)),
+        };
+    }
+}
+
+/// The error for a method without a suitable codec.
+fn unsupported(id: &[u8], props: Option<&[u8]>) -> CodecError {
+    return match Method::from_coder(id, props) {
+        Method::Unknown(id) => CodecError::InvalidCodecID(id),
+        method => CodecError::UnsupportedMethod(method),
+    };
+}
+
+impl fmt::Debug for CodecRegistry {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let ids: Vec<&Vec<u8>> = self.factories.iter().map(|(id, _)| id).collect();
+        return f.debug_struct("CodecRegistry").field("ids", &ids).finish();
+    }
+}
+
+impl Default for CodecRegistry {
+    fn default() -> Self {
+        return CodecRegistry::new();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use alloc::vec;
+
+    /// Flips every bit, standing in for a private method.
+    struct Invert {}
+
+    impl Codec for Invert {
+        fn decode(&self, data: &[u8]) -> Result<Vec<u8>, CodecError> {
+            return Ok(data.iter().map(|b| !b).collect());
+        }
+    }
+
+    #[test]
+    fn builtin_codecs() {
+        let registry = CodecRegistry::new();
+        let copy = registry.decoder(&[0x00], None).unwrap();
+        assert_eq!(copy.decode(&[1, 2, 3]), Ok(vec![1, 2, 3]));
+        assert_eq!(
+            registry.encoder(&[0x00]).unwrap().encode(&[1, 2, 3]),
+            Ok(vec![1, 2, 3])
+        );
+        let lzma2 = registry.decoder(&[0x21], Some(&[24])).unwrap();
+        // A single stored chunk of 3 bytes
+        let data = [0x01, 0x00, 0x02, b'a', b'b', b'c', 0x00];
+        assert_eq!(lzma2.decode_bounded(&data, 3), Ok(vec![b'a', b'b', b'c']));
+        assert_eq!(lzma2.decode_bounded(&data, 2), Err(CodecError::InvalidData));
+        assert_eq!(
+            copy.decode_bounded(&[1, 2, 3], 2),
+            Err(CodecError::InvalidData)
+        );
+        assert!(matches!(
+            registry.encoder(&[0x21]),
+            Err(CodecError::UnsupportedMethod(Method::Lzma2(None)))
+        ));
+
+        assert!(matches!(
+            registry.decoder(&[0x03, 0x01, 0x01], None),
+            Err(CodecError::UnsupportedMethod(Method::Lzma(None)))
+        ));
+        assert!(matches!(
+            registry.decoder(&[0x7F, 0x01], None),
+            Err(CodecError::InvalidCodecID(_))
+        ));
+        assert!(CodecRegistry::empty().decoder(&[0x00], None).is_err());
+    }
+
+    #[test]
+    fn register_codec() {
+        let mut registry = CodecRegistry::new();
+        registry.register(&[0x7F, 0x01], |_: Option<&[u8]>| {
+            return Ok(Box::new(Invert {}) as Box<dyn Codec>);
+        });
+        let invert = registry.decoder(&[0x7F, 0x01], None).unwrap();
+        assert_eq!(invert.decode(&[0x00, 0xF0]), Ok(vec![0xFF, 0x0F]));
+
+        // Built-ins can be overridden
+        registry.register(&[0x00], |_: Option<&[u8]>| {
+            return Ok(Box::new(Invert {}) as Box<dyn Codec>);
+        });
+        let copy = registry.decoder(&[0x00], None).unwrap();
+        assert_eq!(copy.decode(&[0x00]), Ok(vec![0xFF]));
+    }
+}
@@ -1,13 +1,16 @@
-#![forbid(unsafe_code)]
 //! A crate for interacting with 7zip files.
-//! Archives can currently only be read, but support for writing would be nice.
+//! Archives can be read, and simple uncompressed archives can be written.
 
 #![no_std]
 #![allow(clippy::needless_return)]
 #![forbid(unsafe_code)]
 
 extern crate alloc;
+#[cfg(feature = "std")]
+extern crate std;
 
 mod codec;
+mod options;
 mod parser;
 pub mod read;
+pub mod write;
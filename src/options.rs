@@ -0,0 +1,99 @@
+//! This module implements configuring how archives are read.
+
+use crate::codec::CodecRegistry;
+
+use alloc::sync::Arc;
+use core::fmt;
+
+/// A limit on the resources reading an archive may use.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Limit {
+    TotalUnpackedSize,
+    EntrySize,
+    Entries,
+    HeaderSize,
+    DictionarySize,
+    DecodedHeaderSize,
+    Folders,
+    Coders,
+}
+
+impl fmt::Display for Limit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Limit::TotalUnpackedSize => "total unpacked size",
+            Limit::EntrySize => "entry size",
+            Limit::Entries => "entries",
+            Limit::HeaderSize => "header size",
+            Limit::DictionarySize => "dictionary size",
+            Limit::DecodedHeaderSize => "decoded header size",
+            Limit::Folders => "folders",
+            Limit::Coders => "coders",
+        };
+        return f.write_str(name);
+    }
+}
+
+/// Limits on the resources reading an archive may use, to guard against decompression bombs.
+///
+/// Sizes are in bytes. By default, nothing is limited.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Limits {
+    /// Total size of all folders once decoded, as declared in the header.
+    pub max_total_unpacked_size: u64,
+    /// Size of a single entry, checked when it's extracted.
+    pub max_entry_size: u64,
+    /// Number of files, directories and anti-items.
+    pub max_entries: usize,
+    /// Size of the header, as stored in the archive.
+    pub max_header_size: u64,
+    /// Dictionary (or model) size of any method, checked when it's decoded.
+    pub max_dictionary_size: u64,
+    /// Size of header data stored in encoded streams, once decoded.
+    pub max_decoded_header_size: u64,
+    /// Number of folders, counted separately for the contents and for header data in encoded streams.
+    /// Unlimited by default.
+    pub max_folders: usize,
+    /// Number of coders in a single folder.
+    pub max_coders: usize,
+}
+
+impl Limits {
+    /// Return an error if `value` exceeds the `limit` of `max`.
+    pub(crate) fn check<T: PartialOrd>(value: T, max: T, limit: Limit) -> Result<(), Limit> {
+        if value > max {
+            return Err(limit);
+        }
+        return Ok(());
+    }
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        return Limits {
+            max_total_unpacked_size: u64::MAX,
+            max_entry_size: u64::MAX,
+            max_entries: usize::MAX,
+            max_header_size: u64::MAX,
+            max_dictionary_size: u64::MAX,
+            max_decoded_header_size: u64::MAX,
+            max_folders: usize::MAX,
+            max_coders: usize::MAX,
+        };
+    }
+}
+
+/// Options for reading an archive.
+#[derive(Debug, Clone, Default)]
+pub struct ReadOptions {
+    /// The codecs used to decode the archive's contents.
+    pub registry: Arc<CodecRegistry>,
+    pub limits: Limits,
+    /// Read damaged archives as far as possible, instead of failing on the first problem.
+    ///
+    /// CRC mismatches in the headers are tolerated, a header which the start header doesn't lead to
+    /// is searched for, and folders whose data is missing are skipped.
+    /// The search only covers the last 16 MiB of the archive, or `Limits::max_header_size` if that's smaller.
+    /// The damage is listed in `ArchiveInfo::warnings`.
+    pub recover: bool,
+}
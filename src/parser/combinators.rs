@@ -1,10 +1,10 @@
+use super::err::SevenZParserError;
+
 use alloc::vec::Vec;
 
 use bitvec::prelude::*;
 use either::*;
-use nom::combinator::cond;
 use nom::error::ParseError;
-use nom::multi::count;
 use nom::IResult;
 
 /// Runs the second parser and returns it's output/error only if the first parser succeeds.
@@ -51,17 +51,58 @@ where
 
 /// Runs the given parser for each `true` in the given `BitVec` and pushes a `Some(parser_retval)`.
 /// For each `false`, does not run the parser and pushes a `None`.
-pub fn many_cond_opt<I, O, E, F>(f: F, bv: BitVec) -> impl FnMut(I) -> IResult<I, Vec<Option<O>>, E>
+///
+/// Errors record the index of the entry that failed.
+pub fn many_cond_opt<I, O, F>(
+    mut f: F,
+    bv: BitVec,
+) -> impl FnMut(I) -> IResult<I, Vec<Option<O>>, SevenZParserError<I>>
 where
-    F: nom::Parser<I, O, E> + Clone,
+    F: nom::Parser<I, O, SevenZParserError<I>>,
     I: Clone + PartialEq,
     O: Sized,
-    E: ParseError<I>,
 {
     move |input: I| {
-        let mut iter = bv.iter();
-        let (input, ret): (I, Vec<Option<O>>) =
-            count(cond(*iter.next().unwrap(), f.clone()), bv.len())(input)?;
+        let mut input = input;
+        // The bits may come from a hostile archive, so don't trust them for the allocation
+        let mut ret: Vec<Option<O>> = Vec::with_capacity(bv.len().min(4096));
+        for (i, defined) in bv.iter().enumerate() {
+            if *defined {
+                let entry = input.clone();
+                let (rest, val) = f
+                    .parse(input)
+                    .map_err(|e| e.map(|e| SevenZParserError::add_entry(entry, i, e)))?;
+                input = rest;
+                ret.push(Some(val));
+            } else {
+                ret.push(None);
+            }
+        }
+        return Ok((input, ret));
+    }
+}
+
+/// Like nom's `count()`, but errors record the index of the entry that failed.
+pub fn count_entries<I, O, F>(
+    mut f: F,
+    count: usize,
+) -> impl FnMut(I) -> IResult<I, Vec<O>, SevenZParserError<I>>
+where
+    F: nom::Parser<I, O, SevenZParserError<I>>,
+    I: Clone,
+{
+    move |input: I| {
+        let mut input = input;
+        // The count may come from a hostile archive, so don't trust it for the allocation
+        let mut ret: Vec<O> = Vec::with_capacity(count.min(4096));
+        for i in 0..count {
+            let entry = input.clone();
+            let (rest, val) = f
+                .parse(input)
+                .map_err(|e| e.map(|e| SevenZParserError::add_entry(entry, i, e)))?;
+            input = rest;
+            ret.push(val);
+        }
         return Ok((input, ret));
     }
 }
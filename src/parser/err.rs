@@ -1,13 +1,16 @@
+use crate::codec::CodecError;
+
+use alloc::format;
+use alloc::string::{String, ToString};
 use alloc::vec::Vec;
 use core::convert::{From, TryFrom};
+use core::fmt;
 use nom::error::*;
 
 /// Error type for failed conversions.
 #[derive(Debug, Clone, PartialEq)]
 pub enum SevenZConversionError {
     ToUsize(<usize as TryFrom<u64>>::Error),
-    // Actual FromUtf16Error not saved because it would ruin Clone and PartialEq impl
-    ToString,
 }
 
 /// The types of errors that may be returned by the parser.
@@ -26,15 +29,97 @@ pub enum SevenZParserErrorKind<I> {
     DummyNotAllZeroes,
     CouldNotDetermineNumFolders,
     CouldNotDetermineNumUnpackStreams,
+    // InvalidExternalStreamIndex(index)
+    InvalidExternalStreamIndex(u64),
+    // InvalidExternalData(index)
+    InvalidExternalData(u64),
+    DecodingFailed(CodecError),
+    /// The input ended before the archive did.
+    Incomplete,
+    /// The archive claims more data than the input contains.
+    Truncated,
+    // OffsetOutOfRange(offset, size), for a region whose end can't be represented
+    OffsetOutOfRange(u64, u64),
+    /// A packed header decoded to yet another packed header.
+    EncodedHeaderUnsupported,
+    ExternalFoldersUnsupported,
+    /// Files have no names.
+    MissingNames,
+    // InvalidFilesProperty(id), for a property with fewer entries than files
+    InvalidFilesProperty(u8),
+    /// The archive exceeds one of the configured limits.
+    LimitExceeded(crate::options::Limit),
+    /// Folders refer to pack streams or unpack sizes which don't exist, or their sizes don't add up.
+    InvalidStreamsInfo,
+    /// Different bytes than the format requires, e.g. the wrong property ID.
+    Unexpected {
+        expected: Vec<u8>,
+        found: Vec<u8>,
+    },
+}
+
+/// Damage to an archive which was worked around, when reading it in recovery mode.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Warning {
+    // StartHeaderCrc(expected, got)
+    StartHeaderCrc(u32, u32),
+    // HeaderCrc(expected, got)
+    HeaderCrc(u32, u32),
+    /// The start header was unusable, so the header was found by scanning backwards from the end.
+    /// Contains the header's offset from the start of the archive.
+    HeaderFound(u64),
+    /// The packed streams of the folder with this index lie outside of the archive,
+    /// so the files in it can't be extracted.
+    MissingFolderData(usize),
+}
+
+impl fmt::Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return match self {
+            Warning::StartHeaderCrc(expected, got) => write!(
+                f,
+                "start header CRC mismatch, expected {:08x} but got {:08x}",
+                expected, got
+            ),
+            Warning::HeaderCrc(expected, got) => write!(
+                f,
+                "header CRC mismatch, expected {:08x} but got {:08x}",
+                expected, got
+            ),
+            Warning::HeaderFound(offset) => {
+                write!(
+                    f,
+                    "start header is unusable, found header at offset {}",
+                    offset
+                )
+            }
+            Warning::MissingFolderData(index) => {
+                write!(f, "packed data of folder {} is missing", index)
+            }
+        };
+    }
+}
+
+/// One step on the way to where an error occurred, from the outermost structure inwards.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ErrorContext {
+    /// A structure or field, as named by `nom::error::context()`.
+    Named(&'static str),
+    /// An entry in a list, e.g. the 12th file's name.
+    Entry(usize),
+    /// A builtin nom combinator the error passed through.
+    Nom(ErrorKind),
 }
 
 /// The error type returned by all parsers.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct SevenZParserError<I> {
     /// What kind of error this is
     pub kind: SevenZParserErrorKind<I>,
-    /// All the context we have accumulated from previous errors.
-    pub ctx: Vec<(I, &'static str)>,
+    /// Where the error occurred, if known.
+    pub location: Option<I>,
+    /// All the context we have accumulated from previous errors, innermost first.
+    pub ctx: Vec<(I, ErrorContext)>,
 }
 
 impl<I> ParseError<I> for SevenZParserError<I> {
@@ -42,8 +127,9 @@ impl<I> ParseError<I> for SevenZParserError<I> {
         return SevenZParserError::new(SevenZParserErrorKind::Nom(input, kind));
     }
 
-    fn append(_: I, _: ErrorKind, other: Self) -> Self {
-        other
+    fn append(input: I, kind: ErrorKind, mut other: Self) -> Self {
+        other.ctx.push((input, ErrorContext::Nom(kind)));
+        return other;
     }
 }
 
@@ -52,7 +138,7 @@ impl<I> SevenZParserError<I> {
     pub fn from_err(e: nom::Err<nom::error::Error<I>>) -> Self {
         use nom::Err::*;
         match e {
-            Incomplete(_) => panic!("Encountered incomplete, not sure what to do"),
+            Incomplete(_) => return SevenZParserError::new(SevenZParserErrorKind::Incomplete),
             Error(inner) => return SevenZParserError::from_error_kind(inner.input, inner.code),
             Failure(inner) => return SevenZParserError::from_error_kind(inner.input, inner.code),
         }
@@ -62,14 +148,268 @@ impl<I> SevenZParserError<I> {
     pub fn new(kind: SevenZParserErrorKind<I>) -> Self {
         return SevenZParserError {
             kind,
+            location: None,
             ctx: Vec::new(),
         };
     }
+
+    /// Creates a new error, which occurred at `input`.
+    pub fn at(input: I, kind: SevenZParserErrorKind<I>) -> Self {
+        return SevenZParserError {
+            kind,
+            location: Some(input),
+            ctx: Vec::new(),
+        };
+    }
+
+    /// Records that the error occurred in the `index`th entry of a list starting at `input`.
+    pub fn add_entry(input: I, index: usize, mut other: Self) -> Self {
+        other.ctx.push((input, ErrorContext::Entry(index)));
+        return other;
+    }
+}
+
+impl SevenZParserError<&[u8]> {
+    /// Converts subslices of `archive` into offsets from its start,
+    /// so that the error no longer borrows from the archive data.
+    pub fn into_owned(self, archive: &[u8]) -> SevenZParserError<u64> {
+        let start = archive.as_ptr() as usize;
+        return self.map_input(|i| {
+            let offset = (i.as_ptr() as usize).saturating_sub(start);
+            return offset.min(archive.len()) as u64;
+        });
+    }
+}
+
+impl<I> SevenZParserError<I> {
+    /// Converts the input type of the error, e.g. from subslices to offsets.
+    pub fn map_input<J>(self, mut f: impl FnMut(I) -> J) -> SevenZParserError<J> {
+        let location = self.location.map(&mut f);
+        let ctx = self.ctx.into_iter().map(|(i, c)| (f(i), c)).collect();
+        return SevenZParserError {
+            kind: self.kind.map_input(f),
+            location,
+            ctx,
+        };
+    }
+}
+
+impl SevenZParserError<u64> {
+    /// The offset within the archive at which parsing failed, if known.
+    pub fn offset(&self) -> Option<u64> {
+        if let Some(offset) = self.location {
+            return Some(offset);
+        }
+        if let SevenZParserErrorKind::Nom(offset, _) = self.kind {
+            return Some(offset);
+        }
+        return self.ctx.first().map(|(offset, _)| *offset);
+    }
+
+    /// The structures being parsed when the error occurred, from the outermost inwards,
+    /// e.g. `header files_info > names names > entry 12`.
+    pub fn path(&self) -> String {
+        let mut path = String::new();
+        // The innermost context comes first, but reads better last
+        for (_, ctx) in self.ctx.iter().rev() {
+            if let ErrorContext::Nom(_) = ctx {
+                continue;
+            }
+            if !path.is_empty() {
+                path.push_str(" > ");
+            }
+            path.push_str(&ctx.to_string());
+        }
+        return path;
+    }
+
+    /// Render a multi-line description of the error,
+    /// showing the bytes of the `archive` around where it occurred.
+    pub fn diagnostic(&self, archive: &[u8]) -> String {
+        let mut out = format!("error: {}\n", self.kind);
+        if let Some(offset) = self.offset() {
+            out += &format!("  --> offset {} ({:#x})\n", offset, offset);
+            out += &hexdump(archive, offset, self.highlight_len());
+        }
+        for (i, (offset, ctx)) in self.ctx.iter().rev().enumerate() {
+            let indent = "  ".repeat(i);
+            out += &format!("  {}in {} at offset {}\n", indent, ctx, offset);
+        }
+        return out;
+    }
+
+    /// The number of bytes to highlight at the error's offset.
+    fn highlight_len(&self) -> usize {
+        return match &self.kind {
+            SevenZParserErrorKind::Unexpected { expected, found } => {
+                expected.len().max(found.len()).max(1)
+            }
+            _ => 1,
+        };
+    }
+}
+
+/// Show the row of 16 bytes containing `offset` and the row before it,
+/// with `len` bytes from `offset` marked in a line below.
+fn hexdump(data: &[u8], offset: u64, len: usize) -> String {
+    let offset = usize::try_from(offset).unwrap_or(usize::MAX);
+    let row = offset - offset % 16;
+    let mut out = String::new();
+    for start in [row.checked_sub(16), Some(row)].into_iter().flatten() {
+        let bytes = data
+            .get(start..data.len().min(start.saturating_add(16)))
+            .unwrap_or(&[]);
+        let hex: Vec<String> = bytes.iter().map(|b| format!("{:02x}", b)).collect();
+        out += &format!("  {:08x} | {}\n", start, hex.join(" "));
+    }
+    let column = offset % 16;
+    let marked = "^^ ".repeat(len.min(16 - column));
+    out += &format!(
+        "           | {}{}\n",
+        "   ".repeat(column),
+        marked.trim_end()
+    );
+    return out;
+}
+
+impl fmt::Display for ErrorContext {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return match self {
+            ErrorContext::Named(name) => f.write_str(name),
+            ErrorContext::Entry(index) => write!(f, "entry {}", index),
+            ErrorContext::Nom(kind) => f.write_str(kind.description()),
+        };
+    }
+}
+
+impl fmt::Display for SevenZParserError<u64> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.kind)?;
+        if let Some(offset) = self.offset() {
+            write!(f, " at offset {}", offset)?;
+        }
+        let path = self.path();
+        if !path.is_empty() {
+            write!(f, " (in {})", path)?;
+        }
+        return Ok(());
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for SevenZParserError<u64> {}
+
+impl<I> SevenZParserErrorKind<I> {
+    /// Converts the input type of the error, e.g. from subslices to offsets.
+    pub fn map_input<J>(self, f: impl FnOnce(I) -> J) -> SevenZParserErrorKind<J> {
+        use SevenZParserErrorKind::*;
+        return match self {
+            Nom(i, kind) => Nom(f(i), kind),
+            Crc(expected, got) => Crc(expected, got),
+            InvalidPropertyID(id) => InvalidPropertyID(id),
+            ConversionFailure(e) => ConversionFailure(e),
+            InvalidBooleanByte(value) => InvalidBooleanByte(value),
+            FilesEmptyFileBeforeFilesEmptyStream => FilesEmptyFileBeforeFilesEmptyStream,
+            FilesAntiBeforeFilesEmptyStream => FilesAntiBeforeFilesEmptyStream,
+            DummyNotAllZeroes => DummyNotAllZeroes,
+            CouldNotDetermineNumFolders => CouldNotDetermineNumFolders,
+            CouldNotDetermineNumUnpackStreams => CouldNotDetermineNumUnpackStreams,
+            InvalidExternalStreamIndex(index) => InvalidExternalStreamIndex(index),
+            InvalidExternalData(index) => InvalidExternalData(index),
+            DecodingFailed(e) => DecodingFailed(e),
+            Incomplete => Incomplete,
+            Truncated => Truncated,
+            OffsetOutOfRange(offset, size) => OffsetOutOfRange(offset, size),
+            EncodedHeaderUnsupported => EncodedHeaderUnsupported,
+            ExternalFoldersUnsupported => ExternalFoldersUnsupported,
+            MissingNames => MissingNames,
+            InvalidFilesProperty(id) => InvalidFilesProperty(id),
+            LimitExceeded(limit) => LimitExceeded(limit),
+            InvalidStreamsInfo => InvalidStreamsInfo,
+            Unexpected { expected, found } => Unexpected { expected, found },
+        };
+    }
+}
+
+impl<I> fmt::Display for SevenZParserErrorKind<I> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use SevenZParserErrorKind::*;
+        return match self {
+            Nom(_, kind) => write!(f, "invalid data ({})", kind.description()),
+            Crc(expected, got) => write!(
+                f,
+                "CRC mismatch, expected {:08x} but got {:08x}",
+                expected, got
+            ),
+            InvalidPropertyID(id) => write!(f, "invalid property ID {:#04x}", id),
+            ConversionFailure(e) => write!(f, "{}", e),
+            InvalidBooleanByte(value) => write!(f, "invalid boolean byte {:#04x}", value),
+            FilesEmptyFileBeforeFilesEmptyStream => {
+                write!(f, "empty file property before empty stream property")
+            }
+            FilesAntiBeforeFilesEmptyStream => {
+                write!(f, "anti-item property before empty stream property")
+            }
+            DummyNotAllZeroes => write!(f, "dummy property isn't all zeroes"),
+            CouldNotDetermineNumFolders => write!(f, "could not determine the number of folders"),
+            CouldNotDetermineNumUnpackStreams => {
+                write!(f, "could not determine the number of unpack streams")
+            }
+            InvalidExternalStreamIndex(index) => {
+                write!(f, "invalid external stream index {}", index)
+            }
+            InvalidExternalData(index) => write!(f, "invalid external data at index {}", index),
+            DecodingFailed(e) => write!(f, "decoding failed: {}", e),
+            Incomplete => write!(f, "input ended before the archive did"),
+            Truncated => write!(f, "archive is truncated"),
+            OffsetOutOfRange(offset, size) => write!(
+                f,
+                "region of {} bytes at offset {} is out of range",
+                size, offset
+            ),
+            EncodedHeaderUnsupported => write!(f, "nested encoded headers are not supported"),
+            ExternalFoldersUnsupported => write!(f, "external folders are not supported"),
+            MissingNames => write!(f, "files have no names"),
+            InvalidFilesProperty(id) => {
+                write!(f, "files property {:#04x} has fewer entries than files", id)
+            }
+            LimitExceeded(limit) => write!(f, "{} limit exceeded", limit),
+            InvalidStreamsInfo => write!(f, "inconsistent streams info"),
+            Unexpected { expected, found } => {
+                write!(f, "expected ")?;
+                hex_bytes(f, expected)?;
+                write!(f, " but found ")?;
+                if found.is_empty() {
+                    return write!(f, "end of data");
+                }
+                hex_bytes(f, found)
+            }
+        };
+    }
+}
+
+/// Write `bytes` as space-separated hex.
+fn hex_bytes(f: &mut fmt::Formatter<'_>, bytes: &[u8]) -> fmt::Result {
+    for (i, b) in bytes.iter().enumerate() {
+        if i > 0 {
+            write!(f, " ")?;
+        }
+        write!(f, "{:02x}", b)?;
+    }
+    return Ok(());
+}
+
+impl fmt::Display for SevenZConversionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return match self {
+            SevenZConversionError::ToUsize(_) => write!(f, "value doesn't fit into usize"),
+        };
+    }
 }
 
 impl<I> ContextError<I> for SevenZParserError<I> {
-    fn add_context(_input: I, _ctx: &'static str, mut other: Self) -> Self {
-        other.ctx.push((_input, _ctx));
+    fn add_context(input: I, ctx: &'static str, mut other: Self) -> Self {
+        other.ctx.push((input, ErrorContext::Named(ctx)));
         return other;
     }
 }
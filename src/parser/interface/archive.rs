@@ -1,4 +1,50 @@
+use super::crc::sevenz_crc;
+use super::err::{SevenZParserError, SevenZParserErrorKind, Warning};
+use super::limit_exceeded;
+use super::parsers::{header_or_packed_header, region, resolve_external};
+use super::types::{
+    ArchiveProperties, FilesProperty, Header, PackedHeader, PropertyID, SIGNATURE_HEADER_SIZE_BYTES,
+};
+use crate::codec::CodecRegistry;
+use crate::options::{Limit, Limits, ReadOptions};
+use alloc::sync::Arc;
+use either::{Left, Right};
+
+use alloc::string::{String, ToString};
+use alloc::vec;
 use alloc::vec::Vec;
+use bitvec::prelude::*;
+use widestring::U16String;
+
+/// Information about the archive as a whole.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ArchiveInfo {
+    /// Format version as (major, minor).
+    pub version: (u8, u8),
+    /// Offset of the header from the start of the archive.
+    pub header_offset: u64,
+    pub header_size: u64,
+    /// Size of the archive up to the end of the header.
+    pub physical_size: u64,
+    pub num_folders: usize,
+    pub num_pack_streams: usize,
+    /// Total size of all packed streams.
+    pub packed_size: u64,
+    /// Total size of all files once unpacked.
+    pub unpacked_size: u64,
+    /// Whether any folder contains more than one file.
+    pub solid: bool,
+    /// Whether the header itself is packed.
+    pub encoded_header: bool,
+    /// Whether any folder is encrypted.
+    ///
+    /// Encrypted headers aren't covered, as their folder isn't one of the main streams.
+    pub encrypted: bool,
+    /// Names of all methods used in the archive, in the order they first appear.
+    pub methods: Vec<String>,
+    /// Damage which was worked around, when reading the archive in recovery mode.
+    pub warnings: Vec<Warning>,
+}
 
 /// An abstraction over the low-level 7zip format archive construct.
 ///
@@ -6,23 +52,294 @@ use alloc::vec::Vec;
 #[derive(Debug, Clone)]
 pub struct HighLevelArchive {
     pub files: Vec<super::File>,
+    pub folders: Vec<super::FolderInfo>,
+    /// The archive's comment, unless there is none or it's empty.
+    pub comment: Option<U16String>,
+    /// Archive properties this parser doesn't understand, as raw ID and data.
+    pub unknown_properties: Vec<(u8, Vec<u8>)>,
+    /// Files properties this parser doesn't understand, as raw ID and data.
+    ///
+    /// Note that the data describes all files, not a single one.
+    pub unknown_file_properties: Vec<(u8, Vec<u8>)>,
+    pub info: ArchiveInfo,
+    /// The codecs used to decode the archive's contents.
+    pub registry: Arc<CodecRegistry>,
+    pub limits: Limits,
+}
+
+/// Summarize the archive, with `folders` being those of the main streams.
+fn archive_info(ar: &super::types::Archive, folders: &[super::FolderInfo]) -> ArchiveInfo {
+    let sh = &ar.signature_header;
+    let header_offset = SIGNATURE_HEADER_SIZE_BYTES as u64 + sh.start_header.next_header_offset;
+    let mut methods: Vec<String> = vec![];
+    for coder in folders.iter().flat_map(|f| f.coders.iter()) {
+        let name = coder.method().to_string();
+        if !methods.contains(&name) {
+            methods.push(name);
+        }
+    }
+    return ArchiveInfo {
+        version: (sh.archive_version.major, sh.archive_version.minor),
+        header_offset,
+        header_size: sh.start_header.next_header_size,
+        physical_size: header_offset.saturating_add(sh.start_header.next_header_size),
+        num_folders: folders.len(),
+        num_pack_streams: folders.iter().map(|f| f.packed_streams.len()).sum(),
+        packed_size: folders
+            .iter()
+            .flat_map(|f| f.packed_streams.iter())
+            .map(|s| s.size)
+            .sum(),
+        unpacked_size: folders.iter().map(|f| f.unpack_size).sum(),
+        solid: folders.iter().any(|f| f.num_unpack_streams > 1),
+        encoded_header: ar.header_or_packed_header.is_right(),
+        encrypted: folders
+            .iter()
+            .flat_map(|f| f.coders.iter())
+            .any(|c| c.method().is_encryption()),
+        methods,
+        warnings: vec![],
+    };
+}
+
+/// Make sure the folders' packed streams lie within `input`.
+fn check_packed_streams<'a>(
+    input: &'a [u8],
+    folders: &[super::FolderInfo],
+) -> Result<(), SevenZParserError<&'a [u8]>> {
+    for folder in folders {
+        check_folder_packed_streams(input, folder)?;
+    }
+    return Ok(());
+}
+
+fn check_folder_packed_streams<'a>(
+    input: &'a [u8],
+    folder: &super::FolderInfo,
+) -> Result<(), SevenZParserError<&'a [u8]>> {
+    for packed in &folder.packed_streams {
+        region(input, packed.offset, packed.size)?;
+    }
+    return Ok(());
+}
+
+/// Make sure the number of folders and coders, as well as the folders' total unpack size are within `limits`.
+///
+/// `size_limit` is the limit the unpack size counts towards.
+fn check_folder_limits<I>(
+    folders: &[super::FolderInfo],
+    limits: &Limits,
+    size_limit: Limit,
+) -> Result<(), SevenZParserError<I>> {
+    Limits::check(folders.len(), limits.max_folders, Limit::Folders).map_err(limit_exceeded)?;
+    let max_coders = folders.iter().map(|f| f.coders.len()).max().unwrap_or(0);
+    Limits::check(max_coders, limits.max_coders, Limit::Coders).map_err(limit_exceeded)?;
+    let max_size = match size_limit {
+        Limit::DecodedHeaderSize => limits.max_decoded_header_size,
+        _ => limits.max_total_unpacked_size,
+    };
+    let size = folders
+        .iter()
+        .try_fold(0u64, |acc, f| acc.checked_add(f.unpack_size))
+        .unwrap_or(u64::MAX);
+    Limits::check(size, max_size, size_limit).map_err(limit_exceeded)?;
+    return Ok(());
+}
+
+/// Decode the packed header, which is stored in a folder of it's own.
+///
+/// Errors within the decoded header point to the folder's packed stream.
+fn decode_header<'a>(
+    ph: &PackedHeader,
+    input: &'a [u8],
+    options: &ReadOptions,
+    decode: super::DecodeFolder,
+) -> Result<Header, SevenZParserError<&'a [u8]>> {
+    let folders = super::get_folders_info(&ph.streams_info)?;
+    let folder = match &folders[..] {
+        [folder] => folder,
+        _ => {
+            return Err(SevenZParserError::new(
+                SevenZParserErrorKind::InvalidStreamsInfo,
+            ))
+        }
+    };
+    check_folder_packed_streams(input, folder)?;
+    check_folder_limits(&folders, &options.limits, Limit::DecodedHeaderSize)?;
+    let data = decode(input, folder, options)
+        .map_err(|e| SevenZParserError::new(SevenZParserErrorKind::DecodingFailed(e)))?;
+    if let Some(expected) = folder.crc {
+        let got = sevenz_crc(&data);
+        if got != expected {
+            return Err(SevenZParserError::new(SevenZParserErrorKind::Crc(
+                expected, got,
+            )));
+        }
+    }
+
+    let packed = match folder.packed_streams.first() {
+        Some(p) => region(input, p.offset, p.size)?,
+        None => &input[..0],
+    };
+    return match header_or_packed_header(&data) {
+        Ok((_, Left(hdr))) => Ok(hdr),
+        // 7-Zip never packs a header twice
+        Ok((_, Right(_))) => Err(SevenZParserError::new(
+            SevenZParserErrorKind::EncodedHeaderUnsupported,
+        )),
+        Err(nom::Err::Error(e) | nom::Err::Failure(e)) => {
+            Err(SevenZParserError::new(e.kind.map_input(|_| packed)))
+        }
+        Err(nom::Err::Incomplete(_)) => {
+            Err(SevenZParserError::new(SevenZParserErrorKind::Incomplete))
+        }
+    };
+}
+
+/// Retrieve the comment from the archive properties.
+///
+/// It's stored as UTF-16LE, optionally null-terminated.
+fn archive_comment(ap: &ArchiveProperties) -> Option<U16String> {
+    let (_, data) = ap
+        .property_data
+        .iter()
+        .find(|(id, _)| *id == PropertyID::Comment)?;
+    let comment: Vec<u16> = data
+        .chunks_exact(2)
+        .map(|c| u16::from_le_bytes([c[0], c[1]]))
+        .take_while(|c| *c != 0)
+        .collect();
+    if comment.is_empty() {
+        return None;
+    }
+    return Some(U16String::from_vec(comment));
 }
 
 impl HighLevelArchive {
-    pub fn from_low_level_archive(ar: &super::types::Archive) -> HighLevelArchive {
-        let hdr = ar
-            .header_or_packed_header
-            .clone()
-            .expect_left("Archives with packed headers not yet supported");
+    /// Create the high-level archive from it's low-level counterpart,
+    /// which was parsed from `input`, using the codecs and limits in `options`.
+    ///
+    /// Properties stored in packed streams are decoded using `decode`.
+    pub fn from_low_level_archive<'a>(
+        ar: &super::types::Archive,
+        input: &'a [u8],
+        options: &ReadOptions,
+        decode: super::DecodeFolder,
+    ) -> Result<HighLevelArchive, SevenZParserError<&'a [u8]>> {
+        let hdr = match &ar.header_or_packed_header {
+            Left(hdr) => hdr.clone(),
+            Right((ph, _)) => decode_header(ph, input, options, decode)?,
+        };
+        let limits = &options.limits;
+        // Checked first, as the number of files bounds the number of streams
+        let num_files = hdr.files.as_ref().map(|fi| fi.num_files).unwrap_or(0);
+        Limits::check(num_files, limits.max_entries, Limit::Entries).map_err(limit_exceeded)?;
+        let (folders, streams) = match &hdr.main_streams {
+            Some(si) => (
+                super::get_folders_info(si)?,
+                super::get_file_streams_info(si, num_files)?,
+            ),
+            None => (vec![], vec![]),
+        };
+
+        // When recovering, the files in a folder with missing data just fail to extract
+        let mut warnings = vec![];
+        if options.recover {
+            for (i, folder) in folders.iter().enumerate() {
+                if check_folder_packed_streams(input, folder).is_err() {
+                    warnings.push(Warning::MissingFolderData(i));
+                }
+            }
+        } else {
+            check_packed_streams(input, &folders)?;
+        }
+        check_folder_limits(&folders, limits, Limit::TotalUnpackedSize)?;
+
+        // Properties may be stored in the additional streams instead of the header itself
+        let mut external = vec![];
+        if let Some(si) = &hdr.additional_streams {
+            let additional_folders = super::get_folders_info(si)?;
+            check_packed_streams(input, &additional_folders)?;
+            check_folder_limits(&additional_folders, limits, Limit::DecodedHeaderSize)?;
+            for folder in additional_folders {
+                match decode(input, &folder, options) {
+                    Ok(data) => external.push(data),
+                    Err(e) => {
+                        return Err(SevenZParserError::new(
+                            SevenZParserErrorKind::DecodingFailed(e),
+                        ))
+                    }
+                }
+            }
+        }
+        let fi = match &hdr.files {
+            Some(fi) => Some(resolve_external(fi, &external)?),
+            None => None,
+        };
+
+        // Only files which aren't marked as having an empty stream consume a stream,
+        // and each stream must belong to exactly one of them
         let mut files: Vec<super::File> = Vec::new();
-        for i in 0..hdr.files.clone().unwrap().num_files {
-            files.push(super::File::from_files_and_coders_info(
-                &hdr.files.clone().unwrap(),
-                &hdr.main_streams.clone().unwrap().coders_info.unwrap(),
-                i,
+        let mut streams = streams.into_iter();
+        if let Some(fi) = &fi {
+            let empty_streams = match fi.get_property(FilesProperty::EmptyStream(BitVec::new())) {
+                Some(FilesProperty::EmptyStream(b)) => b,
+                _ => BitVec::new(),
+            };
+            let mut empty_index = 0;
+            for i in 0..fi.num_files {
+                let is_empty = empty_streams.get(i).map(|x| *x).unwrap_or(false);
+                let stream = match is_empty {
+                    true => None,
+                    false => Some(streams.next().ok_or_else(|| {
+                        SevenZParserError::new(SevenZParserErrorKind::InvalidStreamsInfo)
+                    })?),
+                };
+                files.push(super::File::from_files_and_streams_info(
+                    fi,
+                    i,
+                    empty_index,
+                    stream,
+                )?);
+                if is_empty {
+                    empty_index += 1;
+                }
+            }
+        }
+        if streams.next().is_some() {
+            return Err(SevenZParserError::new(
+                SevenZParserErrorKind::InvalidStreamsInfo,
             ));
         }
 
-        return HighLevelArchive { files };
+        let mut info = archive_info(ar, &folders);
+        info.warnings = warnings;
+        let comment = hdr.archive_properties.as_ref().and_then(archive_comment);
+        let unknown_properties = match &hdr.archive_properties {
+            Some(ap) => ap.unknown_properties.clone(),
+            None => vec![],
+        };
+        let unknown_file_properties = match &fi {
+            Some(fi) => fi
+                .properties
+                .iter()
+                .filter_map(|p| match p {
+                    FilesProperty::Unknown(id, data) => Some((*id, data.clone())),
+                    _ => None,
+                })
+                .collect(),
+            None => vec![],
+        };
+
+        return Ok(HighLevelArchive {
+            files,
+            folders,
+            comment,
+            unknown_properties,
+            unknown_file_properties,
+            info,
+            registry: options.registry.clone(),
+            limits: options.limits,
+        });
     }
 }
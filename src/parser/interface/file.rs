@@ -1,10 +1,23 @@
-use core::convert::TryInto;
-
 use super::*;
 
-use alloc::string::String;
 use alloc::vec;
+use bitvec::prelude::*;
 use either::*;
+use widestring::U16String;
+
+/// What kind of entry a `File` is.
+///
+/// Anti-items are used when updating an archive, and mark the entry as deleted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EntryKind {
+    /// A file with contents.
+    File,
+    /// A file without contents.
+    EmptyFile,
+    Directory,
+    AntiFile,
+    AntiDirectory,
+}
 
 /// More-or-less like `FilesInfo`, but for a single file and more high-level.
 #[derive(Debug, Clone)]
@@ -13,89 +26,127 @@ pub struct File {
     pub ctime: Option<u64>,
     pub atime: Option<u64>,
     pub mtime: Option<u64>,
-    // TODO: This should probably be exposed as a wide string instead, so that the user may decide what to do with it.
-    pub name: String,
-    pub stream: FileStreamInfo,
+    /// The name as stored in the archive, which may not be valid UTF-16.
+    pub name: U16String,
+    /// The comment, unless there is none or it's empty.
+    pub comment: Option<U16String>,
+    /// Windows attributes, possibly with p7zip's Unix extension in the high 16 bits.
+    pub attributes: Option<u32>,
+    pub kind: EntryKind,
+    /// Position of the file's data within the original file.
+    pub start_pos: Option<u64>,
+    /// Where the file's contents are stored, unless it's got none.
+    pub stream: Option<FileStreamInfo>,
+}
+
+/// The error for a files property which doesn't describe the file at hand.
+fn invalid<I>(id: PropertyID) -> SevenZParserError<I> {
+    return SevenZParserError::new(SevenZParserErrorKind::InvalidFilesProperty(id.into()));
+}
+
+/// Retrieve the given file's bit from the bit vector property of the given kind.
+fn bit(fi: &FilesInfo, kind: FilesProperty, index: usize) -> bool {
+    let bits = match fi.get_property(kind) {
+        Some(FilesProperty::EmptyStream(b)) => b,
+        Some(FilesProperty::EmptyFile(b)) => b,
+        Some(FilesProperty::Anti(b)) => b,
+        _ => return false,
+    };
+    return bits.get(index).map(|x| *x).unwrap_or(false);
+}
+
+/// Retrieve the given file's value from the time (or start position) property of the given kind.
+fn time<I>(
+    fi: &FilesInfo,
+    kind: FilesProperty,
+    id: PropertyID,
+    index: usize,
+) -> Result<Option<u64>, SevenZParserError<I>> {
+    let times = match fi.get_property(kind) {
+        Some(FilesProperty::CTime(t)) => t,
+        Some(FilesProperty::ATime(t)) => t,
+        Some(FilesProperty::MTime(t)) => t,
+        Some(FilesProperty::StartPos(t)) => t,
+        _ => return Ok(None),
+    };
+    return match times.get(index) {
+        Some(None) => Ok(None),
+        Some(Some(Right(t))) => Ok(Some(*t)),
+        // Too few entries, or an external value which wasn't resolved
+        _ => Err(invalid(id)),
+    };
 }
 
 impl File {
-    pub fn from_files_and_coders_info(fi: &FilesInfo, ci: &CodersInfo, index: usize) -> File {
-        let ctimes = fi.get_property(FilesProperty::CTime(vec![]));
-        let ctime = match ctimes {
-            Some(ct) => match ct {
-                FilesProperty::CTime(t) => match t[index] {
-                    None => None,
-                    Some(eith) => match eith {
-                        Left(_) => panic!("External ctime not yet supported!"),
-                        Right(t) => Some(t),
-                    },
-                },
-                _ => panic!("Unexpected enum variant! This is a bug."),
-            },
-            None => None,
-        };
+    /// Create the file at `index`.
+    ///
+    /// `empty_index` is the file's index among files with an empty stream,
+    /// `stream` the file's stream, if it's got one.
+    pub fn from_files_and_streams_info<I>(
+        fi: &FilesInfo,
+        index: usize,
+        empty_index: usize,
+        stream: Option<FileStreamInfo>,
+    ) -> Result<File, SevenZParserError<I>> {
+        let ctime = time(fi, FilesProperty::CTime(vec![]), PropertyID::CTime, index)?;
+        let atime = time(fi, FilesProperty::ATime(vec![]), PropertyID::ATime, index)?;
+        let mtime = time(fi, FilesProperty::MTime(vec![]), PropertyID::MTime, index)?;
+        let start_pos = time(
+            fi,
+            FilesProperty::StartPos(vec![]),
+            PropertyID::StartPos,
+            index,
+        )?;
 
-        let atimes = fi.get_property(FilesProperty::ATime(vec![]));
-        let atime = match atimes {
-            Some(at) => match at {
-                FilesProperty::CTime(t) => match t[index] {
-                    None => None,
-                    Some(eith) => match eith {
-                        Left(_) => panic!("External atime not yet supported!"),
-                        Right(t) => Some(t),
-                    },
-                },
-                _ => panic!("Unexpected enum variant! This is a bug."),
+        let name = match fi.get_property(FilesProperty::Names(vec![])) {
+            Some(FilesProperty::Names(t)) => match t.get(index) {
+                Some(Right(t)) => t.clone(),
+                _ => return Err(invalid(PropertyID::Name)),
             },
-            None => None,
+            _ => return Err(SevenZParserError::new(SevenZParserErrorKind::MissingNames)),
         };
 
-        let mtimes = fi.get_property(FilesProperty::MTime(vec![]));
-        let mtime = match mtimes {
-            Some(mt) => match mt {
-                FilesProperty::CTime(t) => match t[index] {
-                    None => None,
-                    Some(eith) => match eith {
-                        Left(_) => panic!("External mtime not yet supported!"),
-                        Right(t) => Some(t),
-                    },
-                },
-                _ => panic!("Unexpected enum variant! This is a bug."),
+        let comment = match fi.get_property(FilesProperty::Comments(vec![])) {
+            Some(FilesProperty::Comments(c)) => match c.get(index) {
+                Some(None) => None,
+                Some(Some(Right(c))) => Some(c.clone()),
+                _ => return Err(invalid(PropertyID::Comment)),
             },
-            None => None,
+            _ => None,
         };
 
-        let names = fi.get_property(FilesProperty::Names(vec![]));
-        let name = match names {
-            Some(nm) => match nm {
-                FilesProperty::Names(t) => match t[index].clone() {
-                    Left(_) => panic!("External name not yet supported!"),
-                    Right(t) => Some(t),
-                },
-                _ => panic!("Unexpected enum variant! This is a bug."),
+        let attributes = match fi.get_property(FilesProperty::Attributes(vec![])) {
+            Some(FilesProperty::Attributes(t)) => match t.get(index) {
+                Some(None) => None,
+                Some(Some(Right(t))) => Some(*t),
+                _ => return Err(invalid(PropertyID::WinAttributes)),
             },
-            None => None,
+            _ => None,
         };
-        let name = name.unwrap();
 
-        let stream = get_file_streams_info(ci)[index].clone();
+        let kind = if stream.is_some() {
+            EntryKind::File
+        } else {
+            let is_file = bit(fi, FilesProperty::EmptyFile(BitVec::new()), empty_index);
+            let is_anti = bit(fi, FilesProperty::Anti(BitVec::new()), empty_index);
+            match (is_file, is_anti) {
+                (true, false) => EntryKind::EmptyFile,
+                (false, false) => EntryKind::Directory,
+                (true, true) => EntryKind::AntiFile,
+                (false, true) => EntryKind::AntiDirectory,
+            }
+        };
 
-        return File {
+        return Ok(File {
             ctime,
             atime,
             mtime,
             name,
+            comment,
+            attributes,
+            kind,
+            start_pos,
             stream,
-        };
-    }
-
-    /// Take a subslice of raw file data at the file's stream's starting offset from the given data buffer.
-    ///
-    /// Note that this data is not yet decoded.
-    pub fn subslice_raw<'a>(&self, data: &'a [u8]) -> &'a [u8] {
-        let offset: usize = self.stream.offset.try_into().unwrap();
-        let offset: usize = offset + START_HEADER_SIZE_BYTES;
-        let size: usize = self.stream.size.try_into().unwrap();
-        return &data[offset..offset + size];
+        });
     }
 }
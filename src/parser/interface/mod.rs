@@ -7,7 +7,9 @@
 mod archive;
 mod file;
 mod streams;
-pub use super::err::SevenZParserError;
+pub use super::err::{
+    ErrorContext, SevenZConversionError, SevenZParserError, SevenZParserErrorKind, Warning,
+};
 pub use archive::*;
 pub use file::*;
 pub use streams::*;
@@ -15,18 +17,72 @@ pub use streams::*;
 use super::types::*;
 use super::*;
 
+use crate::codec::CodecError;
+use crate::options::{Limit, Limits, ReadOptions};
+use alloc::vec;
+use alloc::vec::Vec;
 use either::*;
 
+/// The error for an archive which exceeds the given limit.
+pub(crate) fn limit_exceeded<I>(limit: Limit) -> SevenZParserError<I> {
+    return SevenZParserError::new(SevenZParserErrorKind::LimitExceeded(limit));
+}
+
+/// Decodes one of the archive's folders, given the archive's data and the options it's read with.
+///
+/// The parser needs it for properties stored in packed streams, but leaves decoding to the caller.
+pub type DecodeFolder = fn(&[u8], &FolderInfo, &ReadOptions) -> Result<Vec<u8>, CodecError>;
+
 /// The entry point into the `parser` module.
 /// Takes a byte slice, returns the parsed archive therein.
-pub fn parse(input: &[u8]) -> Result<HighLevelArchive, SevenZParserError<&[u8]>> {
-    let archive = match parsers::archive(input) {
-        Ok((_, archive)) => archive,
+///
+/// Errors refer to offsets within `input`, instead of borrowing from it.
+pub fn parse(
+    input: &[u8],
+    decode: DecodeFolder,
+) -> Result<HighLevelArchive, SevenZParserError<u64>> {
+    return parse_with_options(input, &ReadOptions::default(), decode);
+}
+
+/// Like `parse()`, but with the given codecs and limits.
+pub fn parse_with_options(
+    input: &[u8],
+    options: &ReadOptions,
+    decode: DecodeFolder,
+) -> Result<HighLevelArchive, SevenZParserError<u64>> {
+    return parse_borrowed(input, options, decode).map_err(|e| e.into_owned(input));
+}
+
+fn parse_borrowed<'a>(
+    input: &'a [u8],
+    options: &ReadOptions,
+    decode: DecodeFolder,
+) -> Result<HighLevelArchive, SevenZParserError<&'a [u8]>> {
+    // Check the header's size before reading it
+    if let Ok((_, sh)) = parsers::signature_header(input) {
+        Limits::check(
+            sh.start_header.next_header_size,
+            options.limits.max_header_size,
+            Limit::HeaderSize,
+        )
+        .map_err(limit_exceeded)?;
+    }
+    let res = match options.recover {
+        true => parsers::archive_recover(input, options.limits.max_header_size),
+        false => parsers::archive(input).map(|(rest, archive)| (rest, (archive, vec![]))),
+    };
+    let (archive, mut warnings) = match res {
+        Ok((_, res)) => res,
         Err(e) => match e {
-		nom::Err::Incomplete(_) => panic!("Parser reported incomplete. Before streaming support is implemented, this should never happen."),
-		nom::Err::Error(e) => return Err(e),
-		nom::Err::Failure(e) => return Err(e),
-	},
+            nom::Err::Incomplete(_) => {
+                return Err(SevenZParserError::new(SevenZParserErrorKind::Incomplete))
+            }
+            nom::Err::Error(e) => return Err(e),
+            nom::Err::Failure(e) => return Err(e),
+        },
     };
-    return Ok(HighLevelArchive::from_low_level_archive(&archive));
+    let mut ar = HighLevelArchive::from_low_level_archive(&archive, input, options, decode)?;
+    warnings.append(&mut ar.info.warnings);
+    ar.info.warnings = warnings;
+    return Ok(ar);
 }
@@ -3,50 +3,208 @@ use super::*;
 use alloc::vec;
 use alloc::vec::Vec;
 
+/// A packed stream, located somewhere in the archive.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PackedStreamInfo {
+    /// Offset from the start of the archive.
+    pub offset: u64,
+    pub size: u64,
+    pub crc: Option<u32>,
+}
+
+/// Everything needed to decode a single folder.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FolderInfo {
+    pub coders: Vec<Coder>,
+    pub bind_pairs: Vec<(u64, u64)>,
+    /// The packed streams which the folder's coders take as input, in the folder's order.
+    pub packed_streams: Vec<PackedStreamInfo>,
+    /// Unpacked sizes of each of the coders' output streams.
+    pub coders_unpack_sizes: Vec<u64>,
+    /// Size of the folder once fully decoded.
+    pub unpack_size: u64,
+    pub crc: Option<u32>,
+    /// Number of files stored in the folder.
+    pub num_unpack_streams: u64,
+}
+
 /// The goal of this type is to get coder and stream information into a form where
 /// it can be easily iterated over to determine how to get at each file.
 #[derive(Debug, Clone, PartialEq)]
 pub struct FileStreamInfo {
-    pub coder: Coder,
+    /// Index of the folder containing the file.
+    pub folder_index: usize,
+    /// Offset of the file's data within the decoded folder.
     pub offset: u64,
     pub size: u64,
-    pub crc: u32,
+    pub crc: Option<u32>,
 }
 
-/// Retrieve all `FileStreamInfo` (one for each file in the archive).
-///
-/// TODO: Support complex coders
-pub fn get_file_streams_info(ci: &CodersInfo) -> Vec<FileStreamInfo> {
+/// The error for streams info which doesn't add up.
+fn invalid<I>() -> SevenZParserError<I> {
+    return SevenZParserError::new(SevenZParserErrorKind::InvalidStreamsInfo);
+}
+
+/// Retrieve all `FolderInfo` (one for each folder in the streams).
+pub fn get_folders_info<I>(si: &StreamsInfo) -> Result<Vec<FolderInfo>, SevenZParserError<I>> {
+    let ci = match &si.coders_info {
+        Some(ci) => ci,
+        None => return Ok(vec![]),
+    };
     let folders = match ci.folders_or_data_stream_index.clone() {
         Right(folders) => folders,
-        Left(_) => panic!("CodersInfo with external folders is currently unsupported!"),
+        Left(_) => {
+            return Err(SevenZParserError::new(
+                SevenZParserErrorKind::ExternalFoldersUnsupported,
+            ))
+        }
+    };
+
+    // Pack streams are stored back-to-back, starting at pack_pos
+    let mut packed_streams = vec![];
+    if let Some(pi) = &si.pack_info {
+        let mut offset = (SIGNATURE_HEADER_SIZE_BYTES as u64)
+            .checked_add(pi.pack_pos)
+            .ok_or_else(invalid)?;
+        // Every pack stream needs a size, so the count can't exceed what's actually stored
+        let sizes = pi.sizes.as_deref().unwrap_or(&[]);
+        if sizes.len() != pi.num_pack_streams {
+            return Err(invalid());
+        }
+        for (i, size) in sizes.iter().copied().enumerate() {
+            let crc = match &pi.crcs {
+                Some(c) => c.get(i).copied().flatten(),
+                None => None,
+            };
+            packed_streams.push(PackedStreamInfo { offset, size, crc });
+            offset = offset.checked_add(size).ok_or_else(invalid)?;
+        }
+    }
+
+    let num_unpack_streams: Vec<u64> = match &si.substreams_info {
+        Some(SubStreamsInfo {
+            num_unpack_streams_in_folders: Some(n),
+            ..
+        }) => n.clone(),
+        _ => vec![1; folders.len()],
+    };
+
+    let mut res = vec![];
+    let mut first_packed_stream: usize = 0;
+    let mut first_out_stream: usize = 0;
+    for (i, folder) in folders.into_iter().enumerate() {
+        let num_packed_streams = match &folder.packed_streams_indices {
+            Some(indices) => indices.len(),
+            None => 1,
+        };
+        let num_out_streams = folder
+            .coders
+            .iter()
+            .try_fold(0usize, |acc, x| acc.checked_add(x.get_num_out_streams()))
+            .ok_or_else(invalid)?;
+        let out_streams_end = first_out_stream
+            .checked_add(num_out_streams)
+            .ok_or_else(invalid)?;
+        let coders_unpack_sizes = ci
+            .streams_unpack_sizes
+            .get(first_out_stream..out_streams_end)
+            .ok_or_else(invalid)?
+            .to_vec();
+
+        // The folder's final output is the only output stream not bound to another coder's input
+        let main_out_stream = (0..num_out_streams)
+            .find(|x| !folder.bind_pairs.iter().any(|(_, out)| *out == *x as u64))
+            .unwrap_or(0);
+        let unpack_size = *coders_unpack_sizes
+            .get(main_out_stream)
+            .ok_or_else(invalid)?;
+
+        let crc = match &ci.folders_unpack_digests {
+            Some(d) => d.get(i).copied().flatten(),
+            None => None,
+        };
+
+        let packed_streams_end = first_packed_stream
+            .checked_add(num_packed_streams)
+            .ok_or_else(invalid)?;
+        res.push(FolderInfo {
+            coders: folder.coders,
+            bind_pairs: folder.bind_pairs,
+            packed_streams: packed_streams
+                .get(first_packed_stream..packed_streams_end)
+                .ok_or_else(invalid)?
+                .to_vec(),
+            coders_unpack_sizes,
+            unpack_size,
+            crc,
+            num_unpack_streams: num_unpack_streams.get(i).copied().unwrap_or(1),
+        });
+        first_packed_stream = packed_streams_end;
+        first_out_stream = out_streams_end;
+    }
+    return Ok(res);
+}
+
+/// Retrieve all `FileStreamInfo` (one for each non-empty stream in the archive).
+///
+/// Streams belong to folders in the order the folders appear.
+/// Each stream belongs to a file, so there may be at most `max_streams` of them.
+pub fn get_file_streams_info<I>(
+    si: &StreamsInfo,
+    max_streams: usize,
+) -> Result<Vec<FileStreamInfo>, SevenZParserError<I>> {
+    let folders = get_folders_info(si)?;
+    // The counts are taken from the archive, so they must be checked before allocating anything
+    let num_streams = folders
+        .iter()
+        .try_fold(0u64, |acc, f| acc.checked_add(f.num_unpack_streams))
+        .ok_or_else(invalid)?;
+    if num_streams > max_streams as u64 {
+        return Err(invalid());
+    }
+    let mut sizes = match &si.substreams_info {
+        Some(SubStreamsInfo {
+            unpack_sizes: Some(s),
+            ..
+        }) => s.clone().into_iter(),
+        _ => vec![].into_iter(),
+    };
+    let mut digests = match &si.substreams_info {
+        Some(SubStreamsInfo {
+            unknown_digests: Some(d),
+            ..
+        }) => d.clone().into_iter(),
+        _ => vec![].into_iter(),
     };
 
     let mut res = vec![];
-    // Assumption: Files belong to folders in the order they appear (spec doesn't say anything about this)
-    // TODO: Deal with substreams
-    let mut i = 0;
-    for folder in folders {
-        for coder in folder.coders {
-            for _ in 0..coder.get_num_out_streams() {
-                // FIXME: Retrieve from substreams if needed
-                // let crc = ci.folders_unpack_digests.clone().unwrap()[i];
-                let crc = 0;
-                res.push(FileStreamInfo {
-                    coder: coder.clone(),
-                    // FIXME: Actually calculate
-                    offset: 0,
-                    size: ci.streams_unpack_sizes[i],
-                    crc,
-                });
-            }
+    for (i, folder) in folders.iter().enumerate() {
+        let mut offset: u64 = 0;
+        let num_streams = folder.num_unpack_streams;
+        for j in 0..num_streams {
+            // The last stream's size is whatever remains of the folder
+            let size = if j + 1 == num_streams {
+                folder.unpack_size.checked_sub(offset).ok_or_else(invalid)?
+            } else {
+                sizes.next().ok_or_else(invalid)?
+            };
+            let crc = if num_streams == 1 && folder.crc.is_some() {
+                folder.crc
+            } else {
+                digests.next().flatten()
+            };
+            res.push(FileStreamInfo {
+                folder_index: i,
+                offset,
+                size,
+                crc,
+            });
+            offset = offset.checked_add(size).ok_or_else(invalid)?;
         }
-        i += 1;
     }
-    return res;
+    return Ok(res);
 }
 
-/// Turn the vector into one that repeats it's
 #[cfg(test)]
 mod test {
     use super::types;
@@ -54,33 +212,163 @@ mod test {
 
     #[test]
     pub fn get_file_streams_info() {
-        let ci = types::CodersInfo {
-            num_folders: 1,
-            folders_or_data_stream_index: either::Right(vec![types::Folder {
-                coders: vec![types::Coder {
-                    complex: None,
-                    attrs: None,
-                    id: vec![33, 1],
-                }],
-                bind_pairs: vec![],
-                packed_streams_indices: Some(vec![0]),
-            }]),
-            streams_unpack_sizes: vec![15],
-            folders_unpack_digests: None,
+        let si = types::StreamsInfo {
+            pack_info: Some(types::PackInfo {
+                pack_pos: 0,
+                num_pack_streams: 1,
+                sizes: Some(vec![19]),
+                crcs: None,
+            }),
+            coders_info: Some(types::CodersInfo {
+                num_folders: 1,
+                folders_or_data_stream_index: either::Right(vec![types::Folder {
+                    coders: vec![types::Coder {
+                        complex: None,
+                        attrs: Some(vec![0]),
+                        id: vec![33],
+                    }],
+                    bind_pairs: vec![],
+                    packed_streams_indices: None,
+                }]),
+                streams_unpack_sizes: vec![15],
+                folders_unpack_digests: None,
+            }),
+            substreams_info: Some(types::SubStreamsInfo {
+                num_unpack_streams_in_folders: None,
+                unpack_sizes: None,
+                unknown_digests: Some(vec![Some(4206898572)]),
+            }),
         };
         let expected = vec![super::FileStreamInfo {
-            coder: types::Coder {
-                complex: None,
-                attrs: None,
-                id: vec![33, 1],
-            },
+            folder_index: 0,
             offset: 0,
             size: 15,
-            crc: 0,
+            crc: Some(4206898572),
         }];
 
-        let res = super::get_file_streams_info(&ci);
+        let res = super::get_file_streams_info::<&[u8]>(&si, usize::MAX).unwrap();
+
+        assert_eq!(res, expected);
+    }
+
+    #[test]
+    pub fn get_file_streams_info_solid() {
+        let si = types::StreamsInfo {
+            pack_info: Some(types::PackInfo {
+                pack_pos: 0,
+                num_pack_streams: 1,
+                sizes: Some(vec![10]),
+                crcs: None,
+            }),
+            coders_info: Some(types::CodersInfo {
+                num_folders: 1,
+                folders_or_data_stream_index: either::Right(vec![types::Folder {
+                    coders: vec![types::Coder {
+                        complex: None,
+                        attrs: None,
+                        id: vec![0],
+                    }],
+                    bind_pairs: vec![],
+                    packed_streams_indices: None,
+                }]),
+                streams_unpack_sizes: vec![10],
+                folders_unpack_digests: None,
+            }),
+            substreams_info: Some(types::SubStreamsInfo {
+                num_unpack_streams_in_folders: Some(vec![3]),
+                unpack_sizes: Some(vec![2, 5]),
+                unknown_digests: None,
+            }),
+        };
+        let expected = vec![
+            super::FileStreamInfo {
+                folder_index: 0,
+                offset: 0,
+                size: 2,
+                crc: None,
+            },
+            super::FileStreamInfo {
+                folder_index: 0,
+                offset: 2,
+                size: 5,
+                crc: None,
+            },
+            super::FileStreamInfo {
+                folder_index: 0,
+                offset: 7,
+                size: 3,
+                crc: None,
+            },
+        ];
+
+        let res = super::get_file_streams_info::<&[u8]>(&si, usize::MAX).unwrap();
 
         assert_eq!(res, expected);
     }
+
+    #[test]
+    pub fn get_folders_info_inconsistent() {
+        let mut si = types::StreamsInfo {
+            pack_info: Some(types::PackInfo {
+                pack_pos: 0,
+                num_pack_streams: 1,
+                sizes: Some(vec![10]),
+                crcs: None,
+            }),
+            coders_info: Some(types::CodersInfo {
+                num_folders: 1,
+                folders_or_data_stream_index: either::Right(vec![types::Folder {
+                    coders: vec![types::Coder {
+                        complex: None,
+                        attrs: None,
+                        id: vec![0],
+                    }],
+                    bind_pairs: vec![],
+                    packed_streams_indices: None,
+                }]),
+                streams_unpack_sizes: vec![],
+                folders_unpack_digests: None,
+            }),
+            substreams_info: None,
+        };
+        // No unpack size for the folder's output stream
+        assert!(super::get_folders_info::<&[u8]>(&si).is_err());
+
+        // More streams than fit into the folder
+        si.coders_info.as_mut().unwrap().streams_unpack_sizes = vec![10];
+        si.substreams_info = Some(types::SubStreamsInfo {
+            num_unpack_streams_in_folders: Some(vec![2]),
+            unpack_sizes: Some(vec![11]),
+            unknown_digests: None,
+        });
+        assert!(super::get_file_streams_info::<&[u8]>(&si, usize::MAX).is_err());
+
+        // Missing pack stream size
+        si.pack_info.as_mut().unwrap().sizes = Some(vec![]);
+        assert!(super::get_folders_info::<&[u8]>(&si).is_err());
+
+        // A huge number of unpack streams without any sizes
+        si.pack_info.as_mut().unwrap().sizes = Some(vec![10]);
+        si.substreams_info = Some(types::SubStreamsInfo {
+            num_unpack_streams_in_folders: Some(vec![u64::MAX]),
+            unpack_sizes: None,
+            unknown_digests: None,
+        });
+        assert!(super::get_file_streams_info::<&[u8]>(&si, usize::MAX).is_err());
+        si.substreams_info
+            .as_mut()
+            .unwrap()
+            .num_unpack_streams_in_folders = Some(vec![3]);
+        assert!(super::get_file_streams_info::<&[u8]>(&si, 2).is_err());
+        assert!(super::get_file_streams_info::<&[u8]>(&si, 3).is_err());
+        si.substreams_info.as_mut().unwrap().unpack_sizes = Some(vec![4]);
+        assert!(super::get_file_streams_info::<&[u8]>(&si, 3).is_err());
+        si.substreams_info.as_mut().unwrap().unpack_sizes = Some(vec![4, 4]);
+        assert!(super::get_file_streams_info::<&[u8]>(&si, 3).is_ok());
+
+        // A huge number of pack streams without any sizes
+        si.pack_info.as_mut().unwrap().num_pack_streams = usize::MAX;
+        si.pack_info.as_mut().unwrap().sizes = None;
+        assert!(super::get_folders_info::<&[u8]>(&si).is_err());
+    }
 }
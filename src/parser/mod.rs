@@ -5,5 +5,9 @@ mod err;
 mod convert;
 mod interface;
 mod parsers;
+mod serialize;
 mod types;
+pub(crate) use crc::sevenz_crc;
 pub use interface::*;
+pub(crate) use serialize::write_archive;
+pub(crate) use types::*;
@@ -1,4 +1,5 @@
 use super::SevenZResult;
+use crate::parser::err::{SevenZParserError, SevenZParserErrorKind};
 
 use alloc::vec;
 use alloc::vec::Vec;
@@ -7,10 +8,10 @@ use core::cmp::min;
 use bitvec::prelude::*;
 use nom::bits::bits;
 use nom::bits::complete::take;
-use nom::error::context;
+use nom::error::{context, ParseError};
 
 /// Extracts the given number of bits from a byte input into a `BitVec`, dropping any leftover bits from the last byte.
-pub fn take_bitvec(input: &[u8], num_bits: usize) -> SevenZResult<BitVec> {
+pub fn take_bitvec(input: &[u8], num_bits: usize) -> SevenZResult<'_, BitVec> {
     // There's no way I can tell to read into a bitvec directly,
     // so this gross workaround of reading into several usizes is needed
     let usize_size = core::mem::size_of::<usize>() * 8;
@@ -19,24 +20,18 @@ pub fn take_bitvec(input: &[u8], num_bits: usize) -> SevenZResult<BitVec> {
     let mut input_mut = input;
     let mut raw_bits: Vec<usize> = vec![];
     while left > 0 {
+        let num = min(left, usize_size);
         let (input, some_bits): (&[u8], usize) = match context::<_, nom::error::Error<&[u8]>, _, _>(
             "take_bitvec some_bits",
-            bits::<_, _, nom::error::Error<(&[u8], usize)>, _, _>(take(min(left, usize_size))),
+            bits::<_, _, nom::error::Error<(&[u8], usize)>, _, _>(take(num)),
         )(input_mut)
         {
-            Err(e) => panic!("TODO: Cleanup"),
+            Err(e) => return Err(e.map(|e| SevenZParserError::from_error_kind(e.input, e.code))),
             Ok(val) => val,
         };
         raw_bits.push(some_bits);
         input_mut = input;
-
-        if left >= usize_size {
-            left = left - usize_size;
-        } else if left == num_tail_bits {
-            left = 0;
-        } else {
-            panic!("I'm not supposed to be reached, this is a bug");
-        }
+        left -= num;
     }
     let input = input_mut;
 
@@ -63,3 +58,21 @@ pub fn take_bitvec(input: &[u8], num_bits: usize) -> SevenZResult<BitVec> {
         .for_each(|x| x.reverse());
     return Ok((input, bv));
 }
+
+/// A `BitVec` with all `num_bits` bits set, for lists whose items are all defined.
+///
+/// Before allocating, checks that `input` is long enough to hold the items of `item_size` bytes each.
+pub fn all_defined(
+    input: &[u8],
+    num_bits: usize,
+    item_size: usize,
+) -> Result<BitVec, nom::Err<SevenZParserError<&[u8]>>> {
+    match num_bits.checked_mul(item_size) {
+        Some(size) if size <= input.len() => return Ok(BitVec::repeat(true, num_bits)),
+        _ => {
+            return Err(nom::Err::Failure(SevenZParserError::new(
+                SevenZParserErrorKind::Truncated,
+            )))
+        }
+    }
+}
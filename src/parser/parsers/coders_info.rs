@@ -1,14 +1,14 @@
 use super::*;
 
-pub fn coder(input: &[u8]) -> SevenZResult<Coder> {
+pub fn coder(input: &[u8]) -> SevenZResult<'_, Coder> {
     fn is_complex(props: u8) -> bool {
-        (props & 0b0000_1000) > 0
+        (props & 0b0001_0000) > 0
     }
     fn has_attrs(props: u8) -> bool {
-        (props & 0b0000_0100) > 0
+        (props & 0b0010_0000) > 0
     }
     fn id_len(props: u8) -> usize {
-        ((props & 0b1111_0000) >> 4) as usize
+        (props & 0b0000_1111) as usize
     }
 
     // TODO: Error for illegally set bit 7
@@ -39,18 +39,21 @@ pub fn coder(input: &[u8]) -> SevenZResult<Coder> {
     return Ok((input, Coder { complex, attrs, id }));
 }
 
-pub fn folder_coders(input: &[u8]) -> SevenZResult<Vec<Coder>> {
-    let (input, coders_vec) = context(
-        "folder_coders coders",
-        length_count(
-            context("folder_coders num_coders", sevenz_uint64_as_usize),
-            context("folder_coders coders", coder),
-        ),
-    )(input)?;
+pub fn folder_coders(input: &[u8]) -> SevenZResult<'_, Vec<Coder>> {
+    let (input, num_coders) = context("folder_coders num_coders", sevenz_uint64_as_usize)(input)?;
+    let (input, coders_vec) =
+        context("folder_coders coders", count_entries(coder, num_coders))(input)?;
     return Ok((input, coders_vec));
 }
 
-pub fn folder(input: &[u8]) -> SevenZResult<Folder> {
+/// The error for a folder whose stream counts don't add up.
+fn invalid_folder<'a>() -> nom::Err<SevenZParserError<&'a [u8]>> {
+    return nom::Err::Failure(SevenZParserError::new(
+        SevenZParserErrorKind::InvalidStreamsInfo,
+    ));
+}
+
+pub fn folder(input: &[u8]) -> SevenZResult<'_, Folder> {
     let (input, coders_vec) = context("folder coders", folder_coders)(input)?;
 
     let num_out_streams_total: u64 = coders_vec
@@ -59,10 +62,13 @@ pub fn folder(input: &[u8]) -> SevenZResult<Folder> {
             Some(c) => c.num_out_streams,
             None => 1,
         })
-        .sum();
+        .try_fold(0u64, |acc, x| acc.checked_add(x))
+        .ok_or_else(invalid_folder)?;
     let num_out_streams_total: usize = to_usize_or_err!(num_out_streams_total);
 
-    let num_bind_pairs = num_out_streams_total - 1;
+    let num_bind_pairs = num_out_streams_total
+        .checked_sub(1)
+        .ok_or_else(invalid_folder)?;
     let (input, bind_pairs) = context(
         "folder bind_pairs",
         count(pair(sevenz_uint64, sevenz_uint64), num_bind_pairs),
@@ -74,15 +80,18 @@ pub fn folder(input: &[u8]) -> SevenZResult<Folder> {
             Some(c) => c.num_in_streams,
             None => 1,
         })
-        .sum();
+        .try_fold(0u64, |acc, x| acc.checked_add(x))
+        .ok_or_else(invalid_folder)?;
     let num_in_streams_total: usize = to_usize_or_err!(num_in_streams_total);
-    let num_packed_streams = num_in_streams_total - num_bind_pairs;
+    let num_packed_streams = num_in_streams_total
+        .checked_sub(num_bind_pairs)
+        .ok_or_else(invalid_folder)?;
 
-    // TODO: The spec says that it should be num_packed_streams > 1, but in that case we get a leftover byte.
+    // If there's only a single packed stream, it's index is implied (it's the one not bound).
     let (input, packed_streams_indices) = context(
         "folder packed_streams_indices",
         cond(
-            num_packed_streams >= 1,
+            num_packed_streams > 1,
             count(sevenz_uint64, num_packed_streams),
         ),
     )(input)?;
@@ -97,14 +106,14 @@ pub fn folder(input: &[u8]) -> SevenZResult<Folder> {
     ));
 }
 
-pub fn coders_info(input: &[u8]) -> SevenZResult<CodersInfo> {
+pub fn coders_info(input: &[u8]) -> SevenZResult<'_, CodersInfo> {
     let (input, _) = context(
         "coders_info PropertyID::UnPackInfo",
-        tag([PropertyID::UnPackInfo as u8]),
+        expect([PropertyID::UnPackInfo as u8]),
     )(input)?;
     let (input, _) = context(
         "coders_info PropertyID::Folder",
-        tag([PropertyID::Folder as u8]),
+        expect([PropertyID::Folder as u8]),
     )(input)?;
 
     let (input, num_folders) = context("coders_info num_folders", sevenz_uint64_as_usize)(input)?;
@@ -114,30 +123,32 @@ pub fn coders_info(input: &[u8]) -> SevenZResult<CodersInfo> {
     let (input, folders_or_data_stream_index) = either(
         external,
         context("coders_info data_stream_index", sevenz_uint64),
-        context("coders_info folders", count(folder, num_folders)),
+        context("coders_info folders", count_entries(folder, num_folders)),
     )(input)?;
 
     let (input, _) = context(
         "coders_info PropertyID::CodersUnPackSize",
-        tag([PropertyID::CodersUnPackSize as u8]),
+        expect([PropertyID::CodersUnPackSize as u8]),
     )(input)?;
 
     // Read output stream sizes of all folders
-    let all_coders: Vec<Coder> = folders_or_data_stream_index
-        .clone()
-        .right()
-        .unwrap()
-        .iter()
-        .map(|x| x.coders.clone())
-        .flatten()
-        .collect();
+    let folders = match &folders_or_data_stream_index {
+        Right(folders) => folders,
+        Left(_) => {
+            return Err(nom::Err::Failure(SevenZParserError::new(
+                SevenZParserErrorKind::ExternalFoldersUnsupported,
+            )))
+        }
+    };
+    let all_coders: Vec<Coder> = folders.iter().flat_map(|x| x.coders.clone()).collect();
     let num_total_out_streams: u64 = all_coders
         .iter()
         .map(|x| match x.complex {
             Some(c) => c.num_out_streams,
             None => 1,
         })
-        .sum();
+        .try_fold(0u64, |acc, x| acc.checked_add(x))
+        .ok_or_else(invalid_folder)?;
     let num_total_out_streams = to_usize_or_err!(num_total_out_streams);
 
     let (input, streams_unpack_sizes) = context(
@@ -147,10 +158,13 @@ pub fn coders_info(input: &[u8]) -> SevenZResult<CodersInfo> {
 
     let (input, folders_unpack_digests) = context(
         "coders_info unpack_digests",
-        preceded_opt_lazy(tag([PropertyID::CRC as u8]), count(le_u32, num_folders)),
+        preceded_opt_lazy(expect([PropertyID::CRC as u8]), |x| digests(x, num_folders)),
     )(input)?;
 
-    let (input, _) = context("coders_info PropertyID::End", tag([PropertyID::End as u8]))(input)?;
+    let (input, _) = context(
+        "coders_info PropertyID::End",
+        expect([PropertyID::End as u8]),
+    )(input)?;
 
     return Ok((
         input,
@@ -1,13 +1,12 @@
 use super::*;
-use alloc::string::String;
 use bitvec::prelude::*;
 use either::*;
 use widestring::*;
 
-fn empty_stream(input: &[u8], num_files: usize) -> SevenZResult<FilesProperty> {
+fn empty_stream(input: &[u8], num_files: usize) -> SevenZResult<'_, FilesProperty> {
     let (input, _) = context(
         "property_empty_stream PropertyID::EmptyStream",
-        tag([PropertyID::EmptyStream as u8]),
+        expect([PropertyID::EmptyStream as u8]),
     )(input)?;
     let (input, _size) = context("empty_stream size", sevenz_uint64)(input)?;
 
@@ -18,10 +17,10 @@ fn empty_stream(input: &[u8], num_files: usize) -> SevenZResult<FilesProperty> {
     return Ok((input, FilesProperty::EmptyStream(bits)));
 }
 
-fn empty_file(input: &[u8], num_empty_streams: usize) -> SevenZResult<FilesProperty> {
+fn empty_file(input: &[u8], num_empty_streams: usize) -> SevenZResult<'_, FilesProperty> {
     let (input, _) = context(
         "property_empty_file PropertyID::EmptyFile",
-        tag([PropertyID::EmptyFile as u8]),
+        expect([PropertyID::EmptyFile as u8]),
     )(input)?;
     let (input, _size) = context("empty_file size", sevenz_uint64)(input)?;
 
@@ -32,8 +31,8 @@ fn empty_file(input: &[u8], num_empty_streams: usize) -> SevenZResult<FilesPrope
     return Ok((input, FilesProperty::EmptyFile(bits)));
 }
 
-fn anti(input: &[u8], num_empty_streams: usize) -> SevenZResult<FilesProperty> {
-    let (input, _) = context("anti PropertyID::Anti", tag([PropertyID::Anti as u8]))(input)?;
+fn anti(input: &[u8], num_empty_streams: usize) -> SevenZResult<'_, FilesProperty> {
+    let (input, _) = context("anti PropertyID::Anti", expect([PropertyID::Anti as u8]))(input)?;
     let (input, _size) = context("anti size", sevenz_uint64)(input)?;
 
     let (input, bits) = context("anti is_anti bits", |x| take_bitvec(x, num_empty_streams))(input)?;
@@ -41,7 +40,28 @@ fn anti(input: &[u8], num_empty_streams: usize) -> SevenZResult<FilesProperty> {
     return Ok((input, FilesProperty::Anti(bits)));
 }
 
-fn time(input: &[u8], num_files: usize) -> SevenZResult<Vec<Option<FileTime>>> {
+/// Fill in which files have a value defined, if all of them have one.
+///
+/// Before allocating for `num_files`, check that the values of `value_size` bytes each are actually there.
+/// Externally stored values are checked once they're resolved, until then a single `true` stands in for all files.
+fn all_defined_bits(
+    input: &[u8],
+    defined: Option<BitVec>,
+    num_files: usize,
+    data_idx: Option<u64>,
+    value_size: usize,
+) -> Result<BitVec, nom::Err<SevenZParserError<&[u8]>>> {
+    if let Some(d) = defined {
+        return Ok(d);
+    }
+    if data_idx.is_some() {
+        return Ok(BitVec::repeat(true, num_files.min(1)));
+    }
+    return all_defined(input, num_files, value_size);
+}
+
+/// Reads an optionally defined, possibly external u64 per file.
+fn time(input: &[u8], num_files: usize) -> SevenZResult<'_, Vec<Option<FileTime>>> {
     let (input, _size) = context("time size", sevenz_uint64)(input)?;
     // Fill BitVec telling us which files have timestamps defined,
     // or fill it with `true` if all are defined.
@@ -50,105 +70,107 @@ fn time(input: &[u8], num_files: usize) -> SevenZResult<Vec<Option<FileTime>>> {
         !all_defined,
         context("property_time time_defined", |x| take_bitvec(x, num_files)),
     )(input)?;
-    let defined = match defined {
-        Some(d) => d,
-        None => {
-            let bits: Vec<bool> = core::iter::repeat(true).take(num_files).collect();
-            let mut b = BitVec::from_iter(bits);
-            b
-        }
-    };
-
-    // TODO: Actually read externally-stored data (though maybe not here)
+    // Externally-stored data is only read once the additional streams have been decoded
     let (input, external) = context("time external", bool_byte)(input)?;
-    let (input, data_idx) = cond(external, context("time data_idx", le_u64))(input)?;
-    match data_idx {
-        Some(i) => {
-            let all_external = core::iter::repeat(i)
-                .take(num_files)
-                .map::<Option<Either<u64, u64>>, _>(|x| Some(Left(x)))
-                .collect();
-            return Ok((input, all_external));
-        }
-        None => (),
+    let (input, data_idx) = cond(external, context("time data_idx", sevenz_uint64))(input)?;
+    let defined = all_defined_bits(input, defined, num_files, data_idx, 8)?;
+    if let Some(i) = data_idx {
+        let all_external = defined.iter().map(|x| x.then(|| Left(i))).collect();
+        return Ok((input, all_external));
     }
 
     // Read actual timestamps
     let (input, times): (&[u8], Vec<Option<u64>>) = many_cond_opt(le_u64, defined)(input)?;
-    let ret: Vec<Option<Either<u64, u64>>> = times
-        .iter()
-        .map(|x| match x {
-            Some(x) => Some(Right(*x)),
-            None => None,
-        })
-        .collect();
+    let ret: Vec<Option<Either<u64, u64>>> = times.iter().map(|x| x.map(Right)).collect();
     return Ok((input, ret));
 }
 
-fn ctime(input: &[u8], num_files: usize) -> SevenZResult<FilesProperty> {
-    let (input, _) = context("ctime PropertyID::CTime", tag([PropertyID::CTime as u8]))(input)?;
+fn ctime(input: &[u8], num_files: usize) -> SevenZResult<'_, FilesProperty> {
+    let (input, _) = context("ctime PropertyID::CTime", expect([PropertyID::CTime as u8]))(input)?;
     let (input, ctime) = context("ctime time", |x| time(x, num_files))(input)?;
     return Ok((input, FilesProperty::CTime(ctime)));
 }
 
-fn atime(input: &[u8], num_files: usize) -> SevenZResult<FilesProperty> {
-    let (input, _) = context("atime PropertyID::ATime", tag([PropertyID::ATime as u8]))(input)?;
+fn atime(input: &[u8], num_files: usize) -> SevenZResult<'_, FilesProperty> {
+    let (input, _) = context("atime PropertyID::ATime", expect([PropertyID::ATime as u8]))(input)?;
     let (input, atime) = context("atime time", |x| time(x, num_files))(input)?;
     return Ok((input, FilesProperty::ATime(atime)));
 }
 
-fn mtime(input: &[u8], num_files: usize) -> SevenZResult<FilesProperty> {
-    let (input, _) = context("mtime PropertyID::MTime", tag([PropertyID::MTime as u8]))(input)?;
+fn mtime(input: &[u8], num_files: usize) -> SevenZResult<'_, FilesProperty> {
+    let (input, _) = context("mtime PropertyID::MTime", expect([PropertyID::MTime as u8]))(input)?;
     let (input, mtime) = context("mtime time", |x| time(x, num_files))(input)?;
-    return Ok((input, FilesProperty::ATime(mtime)));
+    return Ok((input, FilesProperty::MTime(mtime)));
 }
 
-/// Parse a null-terminated string made of Windows-style UTF-16LE codepoints.
-fn wchar_str(input: &[u8]) -> SevenZResult<String> {
-    // Read until '\0' into temporary data buffer
-    let (input, (mut data, _)) = context("wchar_str data", many_till(le_u16, tag([0, 0])))(input)?;
-    data.push(0);
-    // Convert
-    let win_str = U16CString::from_vec_truncate(data);
-    let res = match win_str.to_string() {
-        Ok(s) => s,
-        Err(_) => {
-            return Err(nom::Err::Error(SevenZParserError::<&[u8]>::new(
-                SevenZParserErrorKind::<&[u8]>::ConversionFailure(SevenZConversionError::ToString),
-            )))
-        }
-    };
-    return Ok((input, res));
+fn start_pos(input: &[u8], num_files: usize) -> SevenZResult<'_, FilesProperty> {
+    let (input, _) = context(
+        "start_pos PropertyID::StartPos",
+        expect([PropertyID::StartPos as u8]),
+    )(input)?;
+    // Stored just like timestamps
+    let (input, start_pos) = context("start_pos positions", |x| time(x, num_files))(input)?;
+    return Ok((input, FilesProperty::StartPos(start_pos)));
 }
 
-fn names(input: &[u8], num_files: usize) -> SevenZResult<FilesProperty> {
-    let (input, _) = context("names PropertyID::Name", tag([PropertyID::Name as u8]))(input)?;
+/// Parse a null-terminated string made of Windows-style UTF-16LE code units.
+///
+/// The string is not required to be valid UTF-16, as Windows doesn't enforce that for file names.
+fn wchar_str(input: &[u8]) -> SevenZResult<'_, U16String> {
+    // Read until '\0'
+    let (input, (data, _)) = context("wchar_str data", many_till(le_u16, tag([0, 0])))(input)?;
+    return Ok((input, U16String::from_vec(data)));
+}
+
+fn names(input: &[u8], num_files: usize) -> SevenZResult<'_, FilesProperty> {
+    let (input, _) = context("names PropertyID::Name", expect([PropertyID::Name as u8]))(input)?;
     let (input, _size) = context("names size", sevenz_uint64)(input)?;
     let (input, external) = context("names external", bool_byte)(input)?;
 
-    // TODO: Actually support external data
-    let (input, data_idx) = cond(external, context("names data_idx", le_u64))(input)?;
-    match data_idx {
-        Some(i) => {
-            let all_external = core::iter::repeat(i)
-                .take(num_files)
-                .map::<Either<u64, String>, _>(|x| Left(x))
-                .collect();
-            return Ok((input, FilesProperty::Names(all_external)));
-        }
-        None => (),
-    };
+    // Externally-stored data is only read once the additional streams have been decoded
+    let (input, data_idx) = cond(external, context("names data_idx", sevenz_uint64))(input)?;
+    if let Some(i) = data_idx {
+        // Don't trust num_files for the allocation before there's data backing it
+        return Ok((input, FilesProperty::Names(vec![Left(i)])));
+    }
 
     // Read actual names
-    let (input, names) = context("names names", count(wchar_str, num_files))(input)?;
+    let (input, names) = context("names names", count_entries(wchar_str, num_files))(input)?;
     let names = names.iter().map(|x| Right(x.clone())).collect();
     return Ok((input, FilesProperty::Names(names)));
 }
 
-fn attrs(input: &[u8], num_files: usize) -> SevenZResult<FilesProperty> {
+/// Per-file comments are stored like attributes, with strings as values and only for files which have one.
+fn comments(input: &[u8], num_files: usize) -> SevenZResult<'_, FilesProperty> {
+    let (input, _) = context(
+        "comments PropertyID::Comment",
+        expect([PropertyID::Comment as u8]),
+    )(input)?;
+    let (input, _size) = context("comments size", sevenz_uint64)(input)?;
+    let (input, all_defined) = context("comments all_defined", bool_byte)(input)?;
+    let (input, defined): (&[u8], Option<BitVec>) = cond(
+        !all_defined,
+        context("comments comments_defined", |x| take_bitvec(x, num_files)),
+    )(input)?;
+    // Externally-stored data is only read once the additional streams have been decoded
+    let (input, external) = context("comments external", bool_byte)(input)?;
+    let (input, data_idx) = cond(external, context("comments data_idx", sevenz_uint64))(input)?;
+    // An empty string is the shortest comment
+    let defined = all_defined_bits(input, defined, num_files, data_idx, 2)?;
+    if let Some(i) = data_idx {
+        let all_external = defined.iter().map(|x| x.then(|| Left(i))).collect();
+        return Ok((input, FilesProperty::Comments(all_external)));
+    }
+
+    let (input, comments) = context("comments comments", many_cond_opt(wchar_str, defined))(input)?;
+    let comments = comments.into_iter().map(|x| x.map(Right)).collect();
+    return Ok((input, FilesProperty::Comments(comments)));
+}
+
+fn attrs(input: &[u8], num_files: usize) -> SevenZResult<'_, FilesProperty> {
     let (input, _) = context(
         "attrs PropertyID::WinAttributes",
-        tag([PropertyID::WinAttributes as u8]),
+        expect([PropertyID::WinAttributes as u8]),
     )(input)?;
     let (input, _size) = context("attrs size", sevenz_uint64)(input)?;
     // Fill BitVec telling us which files have attrs defined,
@@ -158,104 +180,123 @@ fn attrs(input: &[u8], num_files: usize) -> SevenZResult<FilesProperty> {
         !all_defined,
         context("attrs attrs_defined", |x| take_bitvec(x, num_files)),
     )(input)?;
-    let defined = match defined {
-        Some(d) => d,
-        None => {
-            let bits: Vec<bool> = core::iter::repeat(true).take(num_files).collect();
-            let b = BitVec::from_iter(bits);
-            b
-        }
-    };
-
-    // TODO: Actually read externally-stored data (though maybe not here)
+    // Externally-stored data is only read once the additional streams have been decoded
     let (input, external) = context("attrs external", bool_byte)(input)?;
-    let (input, data_idx) = cond(external, context("attrs data_idx", le_u64))(input)?;
-    match data_idx {
-        Some(i) => {
-            let all_external = core::iter::repeat(i)
-                .take(num_files)
-                .map::<Option<Either<u64, u32>>, _>(|x| Some(Left(x)))
-                .collect();
-            return Ok((input, FilesProperty::Attributes(all_external)));
-        }
-        None => (),
+    let (input, data_idx) = cond(external, context("attrs data_idx", sevenz_uint64))(input)?;
+    let defined = all_defined_bits(input, defined, num_files, data_idx, 4)?;
+    if let Some(i) = data_idx {
+        let all_external = defined.iter().map(|x| x.then(|| Left(i))).collect();
+        return Ok((input, FilesProperty::Attributes(all_external)));
     }
 
     // Read actual attrs
     let (input, attrs): (&[u8], Vec<Option<u32>>) = many_cond_opt(le_u32, defined)(input)?;
-    let ret: Vec<Option<Either<u64, u32>>> = attrs
-        .iter()
-        .map(|x| match x {
-            Some(x) => Some(Right(*x)),
-            None => None,
-        })
-        .collect();
+    let ret: Vec<Option<Either<u64, u32>>> = attrs.iter().map(|x| x.map(Right)).collect();
     return Ok((input, FilesProperty::Attributes(ret)));
 }
 
 /// Reads and ignores a dummy property.
 /// These are not documented in 7zFormat.txt, but according to https://sourceforge.net/p/sevenzip/discussion/45797/thread/0f3f75c9/
 /// are used for ensuring alignment as an optimization technique.
+/// Like 7-Zip, padding which isn't all zeroes is rejected.
 /// Always returns `None` to make property() easier to implement.
-fn dummy(input: &[u8]) -> SevenZResult<Option<FilesProperty>> {
-    let (input, _) = context("dummy PropertyID::Dummy", tag([PropertyID::Dummy as u8]))(input)?;
-    let (input, size) = context("dummy size", sevenz_uint64)(input)?;
-    let size = to_usize_or_err!(size);
-    let (input, data) = context("dummy data", count(u8, size))(input)?;
-    for d in data {
-        if d != 0 {
-            return Err(nom::Err::Error(SevenZParserError::<&[u8]>::new(
-                SevenZParserErrorKind::<&[u8]>::DummyNotAllZeroes,
-            )));
-        }
+fn dummy(input: &[u8]) -> SevenZResult<'_, Option<FilesProperty>> {
+    let (input, _) = context("dummy PropertyID::Dummy", expect([PropertyID::Dummy as u8]))(input)?;
+    let (input, size) = context("dummy size", sevenz_uint64_as_usize)(input)?;
+    let (input, data) = context("dummy data", take(size))(input)?;
+    if data.iter().any(|x| *x != 0) {
+        return Err(nom::Err::Failure(SevenZParserError::new(
+            SevenZParserErrorKind::DummyNotAllZeroes,
+        )));
     }
     return Ok((input, None));
 }
 
+/// Reads a property whose contents this parser doesn't understand, using it's size.
+fn unknown(input: &[u8]) -> SevenZResult<'_, Option<FilesProperty>> {
+    let (input, id) = context("unknown id", u8)(input)?;
+    let (input, size) = context("unknown size", sevenz_uint64_as_usize)(input)?;
+    let (input, data) = context("unknown data", take(size))(input)?;
+    return Ok((input, Some(FilesProperty::Unknown(id, Vec::from(data)))));
+}
+
 /// Reads the next property, whatever it may be.
 /// Returns `None` if a dummy was encountered.
 fn property(
     input: &[u8],
     num_files: usize,
     num_empty_streams: usize,
-) -> SevenZResult<Option<FilesProperty>> {
-    let (input, prop) = context(
-        "property",
-        alt((
-            wrap_some(|x| empty_stream(x, num_files)),
-            wrap_some(|x| empty_file(x, num_empty_streams)),
-            wrap_some(|x| anti(x, num_empty_streams)),
-            wrap_some(|x| ctime(x, num_files)),
-            wrap_some(|x| atime(x, num_files)),
-            wrap_some(|x| mtime(x, num_files)),
-            wrap_some(|x| names(x, num_files)),
-            wrap_some(|x| attrs(x, num_files)),
-            dummy,
-        )),
-    )(input)?;
+) -> SevenZResult<'_, Option<FilesProperty>> {
+    let (_, id) = context("property id", peek(u8))(input)?;
+    let id = match PropertyID::try_from(id) {
+        Ok(id) => id,
+        Err(_) => return context("property unknown", unknown)(input),
+    };
+    // Dispatch on the ID, so that errors in known properties aren't mistaken for unknown ones
+    let (input, prop) = match id {
+        PropertyID::EmptyStream => wrap_some(|x| empty_stream(x, num_files))(input)?,
+        PropertyID::EmptyFile => wrap_some(|x| empty_file(x, num_empty_streams))(input)?,
+        PropertyID::Anti => wrap_some(|x| anti(x, num_empty_streams))(input)?,
+        PropertyID::CTime => wrap_some(|x| ctime(x, num_files))(input)?,
+        PropertyID::ATime => wrap_some(|x| atime(x, num_files))(input)?,
+        PropertyID::MTime => wrap_some(|x| mtime(x, num_files))(input)?,
+        PropertyID::Name => wrap_some(|x| names(x, num_files))(input)?,
+        PropertyID::Comment => wrap_some(|x| comments(x, num_files))(input)?,
+        PropertyID::WinAttributes => wrap_some(|x| attrs(x, num_files))(input)?,
+        PropertyID::StartPos => wrap_some(|x| start_pos(x, num_files))(input)?,
+        PropertyID::Dummy => dummy(input)?,
+        _ => context("property unknown", unknown)(input)?,
+    };
     return Ok((input, prop));
 }
 
-pub fn files_info(input: &[u8], num_empty_streams: usize) -> SevenZResult<FilesInfo> {
+pub fn files_info(input: &[u8]) -> SevenZResult<'_, FilesInfo> {
     let (input, _) = context(
         "files_info PropertyID::FilesInfo",
-        tag([PropertyID::FilesInfo as u8]),
+        expect([PropertyID::FilesInfo as u8]),
     )(input)?;
 
     let (input, num_files) = context("files_info num_files", sevenz_uint64_as_usize)(input)?;
 
-    let (input, (files_properties, _)) = context(
-        "files_info files_properties",
-        many_till(
-            |x| property(x, num_files, num_empty_streams),
-            tag([PropertyID::End as u8]),
-        ),
+    // The EmptyFile and Anti properties only have entries for files with an empty stream,
+    // so their length is only known once the EmptyStream property has been read.
+    let mut input = input;
+    let mut num_empty_streams: Option<usize> = None;
+    let mut files_properties = vec![];
+    loop {
+        let (_, next_id) = context("files_info next property", peek(u8))(input)?;
+        if next_id == PropertyID::End as u8 {
+            break;
+        }
+        if num_empty_streams.is_none() {
+            if next_id == PropertyID::EmptyFile as u8 {
+                return Err(nom::Err::Failure(SevenZParserError::new(
+                    SevenZParserErrorKind::FilesEmptyFileBeforeFilesEmptyStream,
+                )));
+            }
+            if next_id == PropertyID::Anti as u8 {
+                return Err(nom::Err::Failure(SevenZParserError::new(
+                    SevenZParserErrorKind::FilesAntiBeforeFilesEmptyStream,
+                )));
+            }
+        }
+
+        let (rest, prop) = context("files_info files_properties", |x| {
+            property(x, num_files, num_empty_streams.unwrap_or(0))
+        })(input)?;
+        input = rest;
+        if let Some(FilesProperty::EmptyStream(bits)) = &prop {
+            num_empty_streams = Some(bits.count_ones());
+        }
+        if let Some(prop) = prop {
+            files_properties.push(prop);
+        }
+    }
+    // Consume the End marker
+    let (input, _) = context(
+        "files_info PropertyID::End",
+        expect([PropertyID::End as u8]),
     )(input)?;
-    let files_properties = files_properties
-        .iter()
-        .filter(|x| x.is_some())
-        .map(|x| x.clone().unwrap())
-        .collect();
 
     return Ok((
         input,
@@ -265,3 +306,127 @@ pub fn files_info(input: &[u8], num_empty_streams: usize) -> SevenZResult<FilesI
         },
     ));
 }
+
+/// Retrieve the decoded additional stream with the given index.
+fn external_data<I>(external: &[Vec<u8>], index: u64) -> Result<&[u8], SevenZParserError<I>> {
+    return match usize::try_from(index).ok().and_then(|i| external.get(i)) {
+        Some(data) => Ok(data),
+        None => Err(SevenZParserError::new(
+            SevenZParserErrorKind::InvalidExternalStreamIndex(index),
+        )),
+    };
+}
+
+fn time_value(input: &[u8]) -> SevenZResult<'_, u64> {
+    return le_u64(input);
+}
+
+fn attr_value(input: &[u8]) -> SevenZResult<'_, u32> {
+    return le_u32(input);
+}
+
+/// Read the values of an optionally defined property from external data, if that's where they're stored.
+///
+/// If the values are defined for all files, they consist of just the data index,
+/// and there's `num_files` values of `value_size` bytes each.
+fn resolve_optional<I, T: Clone>(
+    values: &[Option<Either<u64, T>>],
+    num_files: usize,
+    external: &[Vec<u8>],
+    value: fn(&[u8]) -> SevenZResult<'_, T>,
+    value_size: usize,
+) -> Result<Vec<Option<Either<u64, T>>>, SevenZParserError<I>> {
+    let index = match values.iter().flatten().find_map(|x| x.clone().left()) {
+        Some(i) => i,
+        None => return Ok(values.to_vec()),
+    };
+    let data = external_data(external, index)?;
+    let defined: BitVec = if values.len() < num_files {
+        match num_files.checked_mul(value_size) {
+            Some(size) if size <= data.len() => BitVec::repeat(true, num_files),
+            _ => {
+                return Err(SevenZParserError::new(
+                    SevenZParserErrorKind::InvalidExternalData(index),
+                ))
+            }
+        }
+    } else {
+        values.iter().map(|x| x.is_some()).collect()
+    };
+    let (_, resolved) = match many_cond_opt(value, defined)(data) {
+        Ok(res) => res,
+        Err(_) => {
+            return Err(SevenZParserError::new(
+                SevenZParserErrorKind::InvalidExternalData(index),
+            ))
+        }
+    };
+    return Ok(resolved.into_iter().map(|x| x.map(Right)).collect());
+}
+
+/// Read a list of strings from external data, if that's where they're stored.
+///
+/// Externally stored lists consist of just the data index, the number of strings is `num_files`.
+fn resolve_strings<I>(
+    values: &[Either<u64, U16String>],
+    num_files: usize,
+    external: &[Vec<u8>],
+) -> Result<Vec<Either<u64, U16String>>, SevenZParserError<I>> {
+    let index = match values.iter().find_map(|x| x.as_ref().left()) {
+        Some(i) => *i,
+        None => return Ok(values.to_vec()),
+    };
+    let data = external_data(external, index)?;
+    return match count_entries(wchar_str, num_files)(data) {
+        Ok((_, strings)) => Ok(strings.into_iter().map(Right).collect()),
+        Err(_) => Err(SevenZParserError::new(
+            SevenZParserErrorKind::InvalidExternalData(index),
+        )),
+    };
+}
+
+/// Replace all externally stored properties with the data they refer to.
+///
+/// `external` are the decoded folders of the archive's additional streams,
+/// which the properties' data indices refer to.
+pub fn resolve_external<I>(
+    fi: &FilesInfo,
+    external: &[Vec<u8>],
+) -> Result<FilesInfo, SevenZParserError<I>> {
+    let mut properties = vec![];
+    for prop in fi.properties.iter() {
+        let resolved = match prop {
+            FilesProperty::CTime(t) => {
+                FilesProperty::CTime(resolve_optional(t, fi.num_files, external, time_value, 8)?)
+            }
+            FilesProperty::ATime(t) => {
+                FilesProperty::ATime(resolve_optional(t, fi.num_files, external, time_value, 8)?)
+            }
+            FilesProperty::MTime(t) => {
+                FilesProperty::MTime(resolve_optional(t, fi.num_files, external, time_value, 8)?)
+            }
+            FilesProperty::Attributes(a) => FilesProperty::Attributes(resolve_optional(
+                a,
+                fi.num_files,
+                external,
+                attr_value,
+                4,
+            )?),
+            FilesProperty::StartPos(p) => {
+                FilesProperty::StartPos(resolve_optional(p, fi.num_files, external, time_value, 8)?)
+            }
+            FilesProperty::Names(n) => {
+                FilesProperty::Names(resolve_strings(n, fi.num_files, external)?)
+            }
+            FilesProperty::Comments(c) => {
+                FilesProperty::Comments(resolve_optional(c, fi.num_files, external, wchar_str, 2)?)
+            }
+            _ => prop.clone(),
+        };
+        properties.push(resolved);
+    }
+    return Ok(FilesInfo {
+        num_files: fi.num_files,
+        properties,
+    });
+}
@@ -1,76 +1,77 @@
 use super::*;
 
-pub fn archive_property(input: &[u8]) -> SevenZResult<(PropertyID, &[u8])> {
-    let (input, prop_type) = context("archive_property prop_type", property_id)(input)?;
+/// Read a single archive property.
+///
+/// The ID is returned raw, as newer versions of 7-Zip may use IDs unknown to this parser.
+pub fn archive_property(input: &[u8]) -> SevenZResult<'_, (u8, &[u8])> {
+    let (input, prop_type) = context("archive_property prop_type", u8)(input)?;
     let (input, len) = context("archive_property len", sevenz_uint64_as_usize)(input)?;
     let (input, prop_data) = context("archive_property prop_data", take(len))(input)?;
     return Ok((input, (prop_type, prop_data)));
 }
 
-pub fn archive_properties(input: &[u8]) -> SevenZResult<ArchiveProperties> {
+pub fn archive_properties(input: &[u8]) -> SevenZResult<'_, ArchiveProperties> {
     let (input, _) = context(
         "archive_properties PropertyID::ArchiveProperties",
-        tag([PropertyID::ArchiveProperties as u8]),
+        expect([PropertyID::ArchiveProperties as u8]),
     )(input)?;
     let mut props: Vec<(PropertyID, Vec<u8>)> = vec![];
+    let mut unknown_props: Vec<(u8, Vec<u8>)> = vec![];
+    let mut input = input;
     loop {
-        let (input, (prop_id, prop_data)) = archive_property(input)?;
-        if prop_id == PropertyID::End {
+        // The End marker isn't followed by a size
+        let (rest, end) = context(
+            "archive_properties PropertyID::End",
+            opt(expect([PropertyID::End as u8])),
+        )(input)?;
+        if end.is_some() {
             return Ok((
-                input,
+                rest,
                 ArchiveProperties {
                     property_data: props,
+                    unknown_properties: unknown_props,
                 },
             ));
         }
 
-        props.push((prop_id, Vec::from(prop_data)));
+        let (rest, (prop_id, prop_data)) = archive_property(input)?;
+        match PropertyID::try_from(prop_id) {
+            Ok(id) => props.push((id, Vec::from(prop_data))),
+            Err(_) => unknown_props.push((prop_id, Vec::from(prop_data))),
+        }
+        input = rest;
     }
 }
 
-pub fn header(input: &[u8]) -> SevenZResult<Header> {
-    let (input, _) = context("header PropertyID::Header", tag([PropertyID::Header as u8]))(input)?;
+pub fn header(input: &[u8]) -> SevenZResult<'_, Header> {
+    let (input, _) = context(
+        "header PropertyID::Header",
+        expect([PropertyID::Header as u8]),
+    )(input)?;
 
     let (input, archive_properties) =
         context("header archive_properties", opt(archive_properties))(input)?;
 
     let (input, have_asi) = context(
         "header PropertyID::AdditionalStreamsInfo",
-        opt(tag([PropertyID::AdditionalStreamsInfo as u8])),
+        opt(expect([PropertyID::AdditionalStreamsInfo as u8])),
     )(input)?;
     let (input, additional_streams) = context(
         "header additional_streams",
-        cond(have_asi.is_some(), |x| streams_info(x, None)),
+        cond(have_asi.is_some(), streams_info),
     )(input)?;
 
     let (input, have_msi) = context(
         "header PropertyID::MainStreamsInfo",
-        opt(tag([PropertyID::MainStreamsInfo as u8])),
+        opt(expect([PropertyID::MainStreamsInfo as u8])),
     )(input)?;
-    // Try to retrieve num_folders
-    let num_folders = if additional_streams.is_some()
-        && additional_streams.clone().unwrap().coders_info.is_some()
-    {
-        Some(
-            additional_streams
-                .clone()
-                .unwrap()
-                .coders_info
-                .unwrap()
-                .num_folders,
-        )
-    } else {
-        None
-    };
-
     let (input, main_streams) = context(
         "header main_streams",
-        cond(have_msi.is_some(), |x| streams_info(x, num_folders)),
+        cond(have_msi.is_some(), streams_info),
     )(input)?;
 
-    // FIXME: Pass proper num_empty_streams
-    let (input, files) = context("header files_info", opt(|x| files_info(x, 0)))(input)?;
-    let (input, _) = context("header PropertyID::End", tag([PropertyID::End as u8]))(input)?;
+    let (input, files) = context("header files_info", opt(files_info))(input)?;
+    let (input, _) = context("header PropertyID::End", expect([PropertyID::End as u8]))(input)?;
 
     return Ok((
         input,
@@ -82,3 +83,23 @@ pub fn header(input: &[u8]) -> SevenZResult<Header> {
         },
     ));
 }
+
+pub fn packed_header(input: &[u8]) -> SevenZResult<'_, PackedHeader> {
+    let (input, _) = context(
+        "packed_header PropertyID::EncodedHeader",
+        expect([PropertyID::EncodedHeader as u8]),
+    )(input)?;
+    let (input, streams_info) = context("packed_header streams_info", streams_info)(input)?;
+    return Ok((input, PackedHeader { streams_info }));
+}
+
+/// Read either a plain header, or a packed one which still needs to be decoded.
+pub fn header_or_packed_header(
+    input: &[u8],
+) -> SevenZResult<'_, Either<Header, (PackedHeader, HeaderInfo)>> {
+    let (_, id) = context("header_or_packed_header property_id", peek(u8))(input)?;
+    if id == PropertyID::EncodedHeader as u8 {
+        return map(packed_header, |ph| Right((ph, HeaderInfo {})))(input);
+    }
+    return map(header, Left)(input);
+}
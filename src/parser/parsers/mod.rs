@@ -25,27 +25,26 @@ use alloc::vec::*;
 use core::convert::*;
 
 use either::*;
-use nom::branch::alt;
 use nom::bytes::complete::{tag, take};
 use nom::combinator::{cond, map, opt, peek};
 use nom::error::context;
 use nom::multi::{count, length_count, many_till};
 use nom::number::complete::{le_u16, le_u32, le_u64, u8};
-use nom::sequence::{pair, preceded};
+use nom::sequence::pair;
 
 /// Header magic bytes
-const MAGIC: [u8; 6] = [b'7', b'z', 0xBC, 0xAF, 0x27, 0x1C];
+pub const MAGIC: [u8; 6] = [b'7', b'z', 0xBC, 0xAF, 0x27, 0x1C];
 
 /// Error type that all parsers return.
 pub type SevenZResult<'a, T> = nom::IResult<&'a [u8], T, SevenZParserError<&'a [u8]>>;
 
-pub fn archive_version(input: &[u8]) -> SevenZResult<ArchiveVersion> {
+pub fn archive_version(input: &[u8]) -> SevenZResult<'_, ArchiveVersion> {
     let (input, major) = context("archive_version major", u8)(input)?;
     let (input, minor) = context("archive_version minor", u8)(input)?;
     return Ok((input, ArchiveVersion { major, minor }));
 }
 
-pub fn start_header(input: &[u8]) -> SevenZResult<StartHeader> {
+pub fn start_header(input: &[u8]) -> SevenZResult<'_, StartHeader> {
     let (input, next_header_offset) = context("start_header next_header_offset", le_u64)(input)?;
     let (input, next_header_size) = context("start_header next_header_size", le_u64)(input)?;
     let (input, next_header_crc) = context("start_header next_header_crc", le_u32)(input)?;
@@ -59,8 +58,19 @@ pub fn start_header(input: &[u8]) -> SevenZResult<StartHeader> {
     ));
 }
 
-pub fn signature_header(input: &[u8]) -> SevenZResult<SignatureHeader> {
-    let (input, _) = context("signature_header magic bytes", tag(MAGIC))(input)?;
+pub fn signature_header(input: &[u8]) -> SevenZResult<'_, SignatureHeader> {
+    let (input, (signature_header, calculated_crc)) = signature_header_unchecked(input)?;
+    if calculated_crc != signature_header.start_header_crc {
+        return Err(nom::Err::Failure(SevenZParserError::new(
+            SevenZParserErrorKind::Crc(signature_header.start_header_crc, calculated_crc),
+        )));
+    }
+    return Ok((input, signature_header));
+}
+
+/// Like `signature_header()`, but returning the start header's CRC instead of verifying it.
+pub fn signature_header_unchecked(input: &[u8]) -> SevenZResult<'_, (SignatureHeader, u32)> {
+    let (input, _) = context("signature_header magic bytes", expect(MAGIC))(input)?;
     let (input, archive_version) =
         context("signature_header archive_version", archive_version)(input)?;
     let (input, start_header_crc) = context("signature_header start_header_crc", le_u32)(input)?;
@@ -70,38 +80,32 @@ pub fn signature_header(input: &[u8]) -> SevenZResult<SignatureHeader> {
     )(input)?;
     let (input, start_header) = context("signature_header start_header", start_header)(input)?;
 
-    // Verify CRC
     let calculated_crc = crc::sevenz_crc(raw_start_header);
-    if calculated_crc != start_header_crc {
-        return Err(nom::Err::Failure(SevenZParserError::new(
-            SevenZParserErrorKind::Crc(start_header_crc, calculated_crc),
-        )));
-    }
-
-    return Ok((
-        input,
-        SignatureHeader {
-            archive_version,
-            start_header_crc,
-            start_header,
-        },
-    ));
+    let signature_header = SignatureHeader {
+        archive_version,
+        start_header_crc,
+        start_header,
+    };
+    return Ok((input, (signature_header, calculated_crc)));
 }
 
-pub fn archive(input: &[u8]) -> SevenZResult<Archive> {
+pub fn archive(input: &[u8]) -> SevenZResult<'_, Archive> {
     let (input, signature_header) = context("archive signature_header", signature_header)(input)?;
 
     // Skip ahead to beginning of next header
     // FIXME: It's probably legitimate that this header is located at the end-of-file,
     // which would be beyond the range indexable by a usize on 16 or 32-bit platforms.
     // Nonetheless, this will only be relevant once streaming is implemented.
-    let header_offset = to_usize_or_err!(signature_header.start_header.next_header_offset);
-    let header_input = &input[header_offset..];
-    // TODO: Support packed headers
-    let (header_input, header) = context("archive header", header)(header_input)?;
+    let start_header = &signature_header.start_header;
+    let header_data = region(
+        input,
+        start_header.next_header_offset,
+        start_header.next_header_size,
+    )
+    .map_err(nom::Err::Failure)?;
+    let (header_input, header_or_packed_header) =
+        context("archive header", header_or_packed_header)(header_data)?;
     // Verify header CRC
-    let header_size = input.len() - header_input.len();
-    let header_data = &input[header_offset..header_offset + header_size];
     let header_crc = crc::sevenz_crc(header_data);
     let expected_header_crc = signature_header.start_header.next_header_crc;
     if header_crc != expected_header_crc {
@@ -112,41 +116,197 @@ pub fn archive(input: &[u8]) -> SevenZResult<Archive> {
 
     let archive = Archive {
         signature_header,
-        header_or_packed_header: Left(header),
+        header_or_packed_header,
     };
     return Ok((header_input, archive));
 }
 
-pub fn bool_byte(input: &[u8]) -> SevenZResult<bool> {
-    let (input, byte) = context("bool_byte byte", u8)(input)?;
+/// Like `archive()`, but working around a damaged start header or CRC mismatches,
+/// which are reported as warnings instead.
+///
+/// If the header can't be read using the start header,
+/// it's searched for backwards from the end of `input`, up to `max_header_size` bytes.
+pub fn archive_recover(
+    input: &[u8],
+    max_header_size: u64,
+) -> SevenZResult<'_, (Archive, Vec<Warning>)> {
+    let mut warnings = vec![];
+    let from_start_header = signature_header_unchecked(input).and_then(|(rest, (sh, crc))| {
+        if crc != sh.start_header_crc {
+            warnings.push(Warning::StartHeaderCrc(sh.start_header_crc, crc));
+        }
+        let start_header = &sh.start_header;
+        let header_data = region(
+            rest,
+            start_header.next_header_offset,
+            start_header.next_header_size,
+        )
+        .map_err(nom::Err::Failure)?;
+        let (_, header) = context("archive header", header_or_packed_header)(header_data)?;
+        return Ok((sh, header, header_data));
+    });
+    let (signature_header, header, header_data) = match from_start_header {
+        Ok(res) => res,
+        Err(e) => match find_header(input, max_header_size) {
+            Some((sh, header, header_data)) => {
+                let offset =
+                    SIGNATURE_HEADER_SIZE_BYTES as u64 + sh.start_header.next_header_offset;
+                warnings.push(Warning::HeaderFound(offset));
+                (sh, Left(header), header_data)
+            }
+            None => return Err(e),
+        },
+    };
+
+    let header_crc = crc::sevenz_crc(header_data);
+    let expected_header_crc = signature_header.start_header.next_header_crc;
+    if header_crc != expected_header_crc {
+        warnings.push(Warning::HeaderCrc(expected_header_crc, header_crc));
+    }
+
+    let archive = Archive {
+        signature_header,
+        header_or_packed_header: header,
+    };
+    return Ok((&header_data[header_data.len()..], (archive, warnings)));
+}
+
+/// How far back from the end `find_header()` searches, unless `max_header_size` is smaller.
+const MAX_RECOVERED_HEADER_SIZE: u64 = 16 << 20;
+
+/// How many candidates `find_header()` tries to parse, as each attempt may take linear time.
+const MAX_HEADER_CANDIDATES: usize = 64;
+
+/// Whether a header could start at `pos`, judging by it's first two bytes.
+fn is_header_candidate(input: &[u8], pos: usize) -> bool {
+    const FIRST_PROPERTIES: [PropertyID; 5] = [
+        PropertyID::End,
+        PropertyID::ArchiveProperties,
+        PropertyID::AdditionalStreamsInfo,
+        PropertyID::MainStreamsInfo,
+        PropertyID::FilesInfo,
+    ];
+    return input[pos] == PropertyID::Header as u8
+        && input
+            .get(pos + 1)
+            .is_some_and(|b| FIRST_PROPERTIES.iter().any(|p| *p as u8 == *b));
+}
+
+/// Search backwards from the end of `input` for a header which extends up to it's end.
+///
+/// Only the last `MAX_RECOVERED_HEADER_SIZE` bytes are searched,
+/// and the search gives up after `MAX_HEADER_CANDIDATES` failed attempts, to keep it linear.
+/// Returns a signature header pointing to it, the header and it's raw data.
+fn find_header(input: &[u8], max_header_size: u64) -> Option<(SignatureHeader, Header, &[u8])> {
+    let max_size = max_header_size.min(MAX_RECOVERED_HEADER_SIZE);
+    let max_size = usize::try_from(max_size).unwrap_or(usize::MAX);
+    let first = SIGNATURE_HEADER_SIZE_BYTES.max(input.len().saturating_sub(max_size));
+    let (pos, header) = (first..input.len())
+        .rev()
+        .filter(|pos| is_header_candidate(input, *pos))
+        .take(MAX_HEADER_CANDIDATES)
+        .find_map(|pos| match header(&input[pos..]) {
+            Ok(([], header)) => Some((pos, header)),
+            _ => None,
+        })?;
+    let header_data = &input[pos..];
+
+    // Keep the version if at least the magic bytes are intact
+    let archive_version = match input.strip_prefix(&MAGIC[..]) {
+        Some([major, minor, ..]) => ArchiveVersion {
+            major: *major,
+            minor: *minor,
+        },
+        _ => ArchiveVersion { major: 0, minor: 4 },
+    };
+    let start_header = StartHeader {
+        next_header_offset: (pos - SIGNATURE_HEADER_SIZE_BYTES) as u64,
+        next_header_size: header_data.len() as u64,
+        next_header_crc: crc::sevenz_crc(header_data),
+    };
+    let mut raw_start_header = Vec::with_capacity(START_HEADER_SIZE_BYTES);
+    raw_start_header.extend_from_slice(&start_header.next_header_offset.to_le_bytes());
+    raw_start_header.extend_from_slice(&start_header.next_header_size.to_le_bytes());
+    raw_start_header.extend_from_slice(&start_header.next_header_crc.to_le_bytes());
+    let signature_header = SignatureHeader {
+        archive_version,
+        start_header_crc: crc::sevenz_crc(&raw_start_header),
+        start_header,
+    };
+    return Some((signature_header, header, header_data));
+}
+
+/// Get the `size` bytes at `offset` in `input`, as described by the archive.
+///
+/// Offsets and sizes come straight from the archive, so they may point beyond the input or overflow.
+pub fn region<I>(input: &[u8], offset: u64, size: u64) -> Result<&[u8], SevenZParserError<I>> {
+    let end = match offset.checked_add(size) {
+        Some(end) => end,
+        None => {
+            return Err(SevenZParserError::new(
+                SevenZParserErrorKind::OffsetOutOfRange(offset, size),
+            ))
+        }
+    };
+    if end > input.len() as u64 {
+        return Err(SevenZParserError::new(SevenZParserErrorKind::Truncated));
+    }
+    return Ok(&input[offset as usize..end as usize]);
+}
+
+/// Like nom's `tag()`, but reporting the expected and found bytes on a mismatch.
+pub fn expect<const N: usize>(expected: [u8; N]) -> impl Fn(&[u8]) -> SevenZResult<'_, &[u8]> {
+    move |input: &[u8]| {
+        if input.starts_with(&expected) {
+            return Ok((&input[N..], &input[..N]));
+        }
+        let found = &input[..N.min(input.len())];
+        return Err(nom::Err::Error(SevenZParserError::at(
+            input,
+            SevenZParserErrorKind::Unexpected {
+                expected: Vec::from(expected),
+                found: Vec::from(found),
+            },
+        )));
+    }
+}
+
+pub fn bool_byte(input: &[u8]) -> SevenZResult<'_, bool> {
+    let (rest, byte) = context("bool_byte byte", u8)(input)?;
     return match byte {
-        0 => Ok((input, false)),
-        1 => Ok((input, true)),
-        _ => Err(nom::Err::Error(SevenZParserError::new(
+        0 => Ok((rest, false)),
+        1 => Ok((rest, true)),
+        _ => Err(nom::Err::Error(SevenZParserError::at(
+            input,
             SevenZParserErrorKind::InvalidBooleanByte(byte),
         ))),
     };
 }
 
-pub fn property_id(input: &[u8]) -> SevenZResult<PropertyID> {
-    let (input, p_u8) = context("property_id", u8)(input)?;
+pub fn property_id(input: &[u8]) -> SevenZResult<'_, PropertyID> {
+    let (rest, p_u8) = context("property_id", u8)(input)?;
     match PropertyID::try_from(p_u8) {
-        Ok(p) => return Ok((input, p)),
+        Ok(p) => return Ok((rest, p)),
         Err(_) => {
-            return Err(nom::Err::Error(SevenZParserError::new(
+            return Err(nom::Err::Error(SevenZParserError::at(
+                input,
                 SevenZParserErrorKind::InvalidPropertyID(p_u8),
             )))
         }
     }
 }
 
-pub fn tag_property_id(input: &[u8], id: PropertyID) -> SevenZResult<PropertyID> {
-    let (input, p) = context("tag_property_id", property_id)(input)?;
+pub fn tag_property_id(input: &[u8], id: PropertyID) -> SevenZResult<'_, PropertyID> {
+    let (rest, p) = context("tag_property_id", property_id)(input)?;
     if p == id {
-        return Ok((input, p));
+        return Ok((rest, p));
     } else {
-        return Err(nom::Err::Error(SevenZParserError::new(
-            SevenZParserErrorKind::Nom(input, nom::error::ErrorKind::Tag),
+        return Err(nom::Err::Error(SevenZParserError::at(
+            input,
+            SevenZParserErrorKind::Unexpected {
+                expected: vec![id as u8],
+                found: vec![p as u8],
+            },
         )));
     }
 }
@@ -5,7 +5,7 @@ use nom::number::complete::u8;
 
 /// 7zip uses a weird packed integer format to represent some u64 values.
 /// Parse that and convert it to a normal u64 in native endianness.
-pub fn sevenz_uint64(input: &[u8]) -> SevenZResult<u64> {
+pub fn sevenz_uint64(input: &[u8]) -> SevenZResult<'_, u64> {
     fn count_leading_ones(b: u8) -> usize {
         let mut num: usize = 0;
         for shift in 0..8 {
@@ -27,12 +27,15 @@ pub fn sevenz_uint64(input: &[u8]) -> SevenZResult<u64> {
         input_mut = input2;
         val += (next_byte as u64) << (i * 8);
     }
-    val += (((first_byte as u64) & ((1 << (8 - (leading_ones as u64))) - 1)) << (leading_ones * 8))
-        as u64;
+    // With 8 leading ones, the first byte holds no value bits
+    if leading_ones < 8 {
+        val +=
+            ((first_byte as u64) & ((1 << (8 - (leading_ones as u64))) - 1)) << (leading_ones * 8);
+    }
     return Ok((input_mut, val));
 }
 /// Like sevenz_uint64, but convert to usize and return an error if the conversion fails.
-pub fn sevenz_uint64_as_usize(input: &[u8]) -> SevenZResult<usize> {
+pub fn sevenz_uint64_as_usize(input: &[u8]) -> SevenZResult<'_, usize> {
     let (input, as_u64) = context("sevenz_uint64_as_usize as_u64", sevenz_uint64)(input)?;
     let as_usize = to_usize_or_err!(as_u64);
     return Ok((input, as_usize));
@@ -1,9 +1,10 @@
 use super::*;
+use bitvec::prelude::*;
 
-pub fn pack_info(input: &[u8]) -> SevenZResult<PackInfo> {
+pub fn pack_info(input: &[u8]) -> SevenZResult<'_, PackInfo> {
     let (input, _) = context(
         "pack_info PropertyID::PackInfo",
-        tag([PropertyID::PackInfo as u8]),
+        expect([PropertyID::PackInfo as u8]),
     )(input)?;
     let (input, pack_pos) = context("pack_info pack_pos", sevenz_uint64)(input)?;
     let (input, num_pack_streams) =
@@ -22,76 +23,107 @@ pub fn pack_info(input: &[u8]) -> SevenZResult<PackInfo> {
         "pack_info crcs",
         preceded_opt_lazy(
             |x| tag_property_id(x, PropertyID::CRC),
-            count(le_u32, num_pack_streams),
+            |x| digests(x, num_pack_streams),
         ),
     )(input)?;
 
-    let (input, _) = context("pack_info PropertyID::End", tag([PropertyID::End as u8]))(input)?;
+    let (input, _) = context("pack_info PropertyID::End", expect([PropertyID::End as u8]))(input)?;
     return Ok((
         input,
         PackInfo {
             pack_pos,
             num_pack_streams,
-            sizes: sizes,
-            crcs: crcs,
+            sizes,
+            crcs,
         },
     ));
 }
 
-pub fn substreams_info(
-    input: &[u8],
-    num_folders: usize,
-    num_unknown_crcs: usize,
-) -> SevenZResult<SubStreamsInfo> {
+/// Reads a list of CRCs, some of which may be left undefined.
+pub fn digests(input: &[u8], num_digests: usize) -> SevenZResult<'_, Vec<Option<u32>>> {
+    // Fill BitVec telling us which digests are defined,
+    // or fill it with `true` if all are defined.
+    let (input, all_defined) = context("digests all_defined", bool_byte)(input)?;
+    let (input, defined): (&[u8], Option<BitVec>) = cond(
+        !all_defined,
+        context("digests defined", |x| take_bitvec(x, num_digests)),
+    )(input)?;
+    let defined = match defined {
+        Some(d) => d,
+        None => super::all_defined(input, num_digests, 4)?,
+    };
+
+    let (input, crcs) = context("digests crcs", many_cond_opt(le_u32, defined))(input)?;
+    return Ok((input, crcs));
+}
+
+/// Add up counts from the archive, which may overflow.
+fn checked_sum<'a>(
+    mut counts: impl Iterator<Item = u64>,
+) -> Result<u64, nom::Err<SevenZParserError<&'a [u8]>>> {
+    return counts
+        .try_fold(0u64, |acc, x| acc.checked_add(x))
+        .ok_or(nom::Err::Failure(SevenZParserError::new(
+            SevenZParserErrorKind::InvalidStreamsInfo,
+        )));
+}
+
+/// Read a SubStreams Info structure.
+/// The folders it describes must already be known from the preceding Coders Info structure.
+pub fn substreams_info<'a>(input: &'a [u8], ci: &CodersInfo) -> SevenZResult<'a, SubStreamsInfo> {
     let (input, _) = context(
         "substreams_info PropertyID::SubStreamsInfo",
-        tag([PropertyID::SubStreamsInfo as u8]),
+        expect([PropertyID::SubStreamsInfo as u8]),
     )(input)?;
 
     let (input, num_unpack_streams_in_folders) = context(
         "substreams_info num_unpack_streams_in_folders",
         preceded_opt_lazy(
-            tag([PropertyID::NumUnPackStream as u8]),
-            count(sevenz_uint64, num_folders),
+            expect([PropertyID::NumUnPackStream as u8]),
+            count(sevenz_uint64, ci.num_folders),
         ),
     )(input)?;
+    // Each folder contains a single stream unless stated otherwise
+    let num_streams: Vec<u64> = match num_unpack_streams_in_folders.clone() {
+        Some(n) => n,
+        None => vec![1; ci.num_folders],
+    };
 
-    // Fail if we'd expect a size block next, but don't know how long it'd be
-    let will_have_unpack_sizes =
-        tag::<[u8; 1], &[u8], SevenZParserError<&[u8]>>([PropertyID::Size as u8])(input).is_ok();
-    let total_streams = to_usize_or_err!(if will_have_unpack_sizes {
-        let num: Vec<u64> = if num_unpack_streams_in_folders.clone().is_some() {
-            num_unpack_streams_in_folders.clone().unwrap()
-        } else {
-            return Err(nom::Err::Failure(SevenZParserError::new(
-                SevenZParserErrorKind::CouldNotDetermineNumUnpackStreams,
-            )));
-        };
-        let v: u64 = num.iter().sum();
-        v
-    } else {
-        0
-    });
-
+    // The size of the last stream in each folder is implied by the folder's unpack size
+    let num_sizes = checked_sum(num_streams.iter().map(|x| x.saturating_sub(1)))?;
+    let num_sizes = to_usize_or_err!(num_sizes);
     let (input, unpack_sizes) = context(
         "substreams_info unpack_sizes",
         preceded_opt_lazy(
-            tag([PropertyID::Size as u8]),
-            count(sevenz_uint64, total_streams),
+            expect([PropertyID::Size as u8]),
+            count(sevenz_uint64, num_sizes),
         ),
     )(input)?;
 
+    // Streams which are alone in a folder that already has a CRC don't get another one
+    let num_unknown_crcs = num_streams.iter().enumerate().map(|(i, n)| {
+        let folder_has_crc = match &ci.folders_unpack_digests {
+            Some(d) => d.get(i).map(|x| x.is_some()).unwrap_or(false),
+            None => false,
+        };
+        if *n == 1 && folder_has_crc {
+            0
+        } else {
+            *n
+        }
+    });
+    let num_unknown_crcs = checked_sum(num_unknown_crcs)?;
+    let num_unknown_crcs = to_usize_or_err!(num_unknown_crcs);
     let (input, unknown_digests) = context(
         "substreams_info unknown_crcs",
-        preceded_opt_lazy(
-            tag([PropertyID::CRC as u8]),
-            count(le_u32, num_unknown_crcs),
-        ),
+        preceded_opt_lazy(expect([PropertyID::CRC as u8]), |x| {
+            digests(x, num_unknown_crcs)
+        }),
     )(input)?;
 
     let (input, _) = context(
         "substreams_info PropertyID::End",
-        tag([PropertyID::End as u8]),
+        expect([PropertyID::End as u8]),
     )(input)?;
 
     return Ok((
@@ -105,34 +137,36 @@ pub fn substreams_info(
 }
 
 /// Read a Streams Info structure.
-/// As the structure appears multiple times, it may not be complete each time.
-/// Therefore, if this isn't the first time reading this structure you have to pass in `num_folders`,
-/// because this info may not be available.
-pub fn streams_info(input: &[u8], num_folders: Option<usize>) -> SevenZResult<StreamsInfo> {
+///
+/// Substreams can only be read if the structure also describes folders,
+/// as otherwise there is no way of knowing how many of them there are.
+pub fn streams_info(input: &[u8]) -> SevenZResult<'_, StreamsInfo> {
     let (input, pack_info_data) = context("streams_info pack_info", opt(pack_info))(input)?;
     let (input, coders_info_data) = context("streams_info coders_info", opt(coders_info))(input)?;
-    // Use given value or retrieve num_folders
-    let num_folders = if num_folders.is_some() {
-        num_folders.unwrap()
-    } else {
-        match coders_info_data.clone() {
-            Some(c) => c.num_folders,
+
+    let has_substreams_info =
+        tag::<[u8; 1], &[u8], SevenZParserError<&[u8]>>([PropertyID::SubStreamsInfo as u8])(input)
+            .is_ok();
+    let (input, substreams_info_data) = if has_substreams_info {
+        let ci = match &coders_info_data {
+            Some(c) => c,
             None => {
                 return Err(nom::Err::Failure(SevenZParserError::new(
                     SevenZParserErrorKind::CouldNotDetermineNumFolders,
                 )))
             }
-        }
+        };
+        let (input, ssi) =
+            context("streams_info substreams_info", |x| substreams_info(x, ci))(input)?;
+        (input, Some(ssi))
+    } else {
+        (input, None)
     };
-
-    // FIXME: Don't hardcode num_unknown_crcs
-    let (input, substreams_info_data) = context(
-        "streams_info substreams_info",
-        opt(|x| substreams_info(x, num_folders, 3)),
+    let (input, _) = context(
+        "streams_info PropertyID::End",
+        expect([PropertyID::End as u8]),
     )(input)?;
-    let (input, _) = context("streams_info PropertyID::End", tag([PropertyID::End as u8]))(input)?;
 
-    // TODO:
     return Ok((
         input,
         StreamsInfo {
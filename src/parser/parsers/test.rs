@@ -130,6 +130,22 @@ fn take_bitvec() {
         assert_eq!(remainder.len(), *bytes_remaining);
         assert_eq!(res, *expected);
     }
+
+    // Not enough input
+    assert!(parsers::take_bitvec(&[0xFF], 9).is_err());
+    assert!(parsers::take_bitvec(&[0xFF; 8], 65).is_err());
+}
+
+#[test]
+fn folder_without_coders() {
+    // No coders means no output stream, so the number of bind pairs would underflow
+    assert!(matches!(
+        parsers::folder(&[0x00]),
+        Err(nom::Err::Failure(super::super::err::SevenZParserError {
+            kind: super::super::err::SevenZParserErrorKind::InvalidStreamsInfo,
+            ..
+        }))
+    ));
 }
 #[test]
 fn pack_info() {
@@ -155,11 +171,11 @@ fn coders_info() {
         folders_or_data_stream_index: either::Right(vec![types::Folder {
             coders: vec![types::Coder {
                 complex: None,
-                attrs: None,
-                id: vec![33, 1],
+                attrs: Some(vec![0]),
+                id: vec![33],
             }],
             bind_pairs: vec![],
-            packed_streams_indices: Some(vec![0]),
+            packed_streams_indices: None,
         }]),
         streams_unpack_sizes: vec![15],
         folders_unpack_digests: None,
@@ -178,12 +194,13 @@ fn substreams_info() {
     let expected = types::SubStreamsInfo {
         num_unpack_streams_in_folders: None,
         unpack_sizes: None,
-        unknown_digests: Some(vec![3224210433, 83886330, 792833]),
+        unknown_digests: Some(vec![Some(4206898572)]),
     };
 
     // Cut parts not relevant here
+    let (_, ci) = parsers::coders_info(&input[59..]).unwrap();
     let input = &input[71..];
-    let (_, res) = parsers::substreams_info(input, 1, 3).unwrap();
+    let (_, res) = parsers::substreams_info(input, &ci).unwrap();
 
     assert_eq!(res, expected);
 }
@@ -194,7 +211,7 @@ fn streams_info() {
     // Cut parts not relevant here
     let input = &input[53..];
 
-    let (_, res) = parsers::streams_info(input, None).unwrap();
+    parsers::streams_info(input).unwrap();
 }
 
 #[test]
@@ -203,15 +220,15 @@ fn files_info() {
     let expected = types::FilesInfo {
         num_files: 1,
         properties: vec![
-            types::FilesProperty::Names(vec![Right(String::from("test.txt"))]),
-            types::FilesProperty::ATime(vec![Some(Right(132708606310000000))]),
+            types::FilesProperty::Names(vec![Right(widestring::U16String::from_str("test.txt"))]),
+            types::FilesProperty::MTime(vec![Some(Right(132708606310000000))]),
             types::FilesProperty::Attributes(vec![Some(Right(2175041568))]),
         ],
     };
     // Cut parts not relevant here
     let input = &input[80..];
 
-    let (_, res) = parsers::files_info(input, 0).unwrap();
+    let (_, res) = parsers::files_info(input).unwrap();
     assert_eq!(res, expected);
 }
 
@@ -222,7 +239,7 @@ fn header() {
     let (input, _) = parsers::signature_header(input).unwrap();
     // From here, header should be in 19 bytes
     let input = &input[19..];
-    let (input, hdr) = parsers::header(input).unwrap();
+    parsers::header(input).unwrap();
 }
 
 #[test]
@@ -253,11 +270,11 @@ fn archive() {
                     folders_or_data_stream_index: Right(vec![types::Folder {
                         coders: vec![types::Coder {
                             complex: None,
-                            attrs: None,
-                            id: vec![33, 1],
+                            attrs: Some(vec![0]),
+                            id: vec![33],
                         }],
                         bind_pairs: vec![],
-                        packed_streams_indices: Some(vec![0]),
+                        packed_streams_indices: None,
                     }]),
                     streams_unpack_sizes: vec![15],
                     folders_unpack_digests: None,
@@ -265,14 +282,16 @@ fn archive() {
                 substreams_info: Some(types::SubStreamsInfo {
                     num_unpack_streams_in_folders: None,
                     unpack_sizes: None,
-                    unknown_digests: Some(vec![3224210433, 83886330, 792833]),
+                    unknown_digests: Some(vec![Some(4206898572)]),
                 }),
             }),
             files: Some(types::FilesInfo {
                 num_files: 1,
                 properties: vec![
-                    types::FilesProperty::Names(vec![Right(String::from("test.txt"))]),
-                    types::FilesProperty::ATime(vec![Some(Right(132708606310000000))]),
+                    types::FilesProperty::Names(vec![Right(widestring::U16String::from_str(
+                        "test.txt",
+                    ))]),
+                    types::FilesProperty::MTime(vec![Some(Right(132708606310000000))]),
                     types::FilesProperty::Attributes(vec![Some(Right(2175041568))]),
                 ],
             }),
@@ -283,3 +302,359 @@ fn archive() {
 
     assert_eq!(res, expected);
 }
+
+#[test]
+fn files_info_empty_file_before_empty_stream() {
+    // FilesInfo, 1 file, EmptyFile (size 1, bits), End
+    let input: &[u8] = &[0x05, 0x01, 0x0F, 0x01, 0x80, 0x00];
+    let res = parsers::files_info(input);
+    assert!(matches!(
+        res,
+        Err(nom::Err::Failure(super::super::err::SevenZParserError {
+            kind: super::super::err::SevenZParserErrorKind::FilesEmptyFileBeforeFilesEmptyStream,
+            ..
+        }))
+    ));
+}
+
+#[test]
+fn files_info_dummy_and_unknown_properties() {
+    // FilesInfo, 1 file, Dummy (2 zero bytes), unknown 0x30 (1 byte), Size (misplaced, 1 byte), End
+    let input: &[u8] = &[
+        0x05, 0x01, 0x19, 0x02, 0x00, 0x00, 0x30, 0x01, 0xAA, 0x09, 0x01, 0xBB, 0x00,
+    ];
+    let (rest, res) = parsers::files_info(input).unwrap();
+    assert!(rest.is_empty());
+    assert_eq!(
+        res,
+        types::FilesInfo {
+            num_files: 1,
+            properties: vec![
+                types::FilesProperty::Unknown(0x30, vec![0xAA]),
+                types::FilesProperty::Unknown(0x09, vec![0xBB]),
+            ],
+        }
+    );
+
+    // Dummy padding which isn't all zeroes
+    let input: &[u8] = &[0x05, 0x01, 0x19, 0x02, 0x00, 0x01, 0x00];
+    assert!(matches!(
+        parsers::files_info(input),
+        Err(nom::Err::Failure(super::super::err::SevenZParserError {
+            kind: super::super::err::SevenZParserErrorKind::DummyNotAllZeroes,
+            ..
+        }))
+    ));
+
+    // Dummy size exceeding the input
+    let input: &[u8] = &[
+        0x05, 0x01, 0x19, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0x0F,
+    ];
+    assert!(parsers::files_info(input).is_err());
+}
+
+#[test]
+fn resolve_external() {
+    let fi = types::FilesInfo {
+        num_files: 2,
+        properties: vec![
+            types::FilesProperty::MTime(vec![None, Some(Left(1))]),
+            types::FilesProperty::Attributes(vec![Some(Right(0x20)), Some(Right(0x10))]),
+        ],
+    };
+    let external = vec![vec![], 132708606310000000u64.to_le_bytes().to_vec()];
+    let expected = types::FilesInfo {
+        num_files: 2,
+        properties: vec![
+            types::FilesProperty::MTime(vec![None, Some(Right(132708606310000000))]),
+            types::FilesProperty::Attributes(vec![Some(Right(0x20)), Some(Right(0x10))]),
+        ],
+    };
+
+    let res: Result<_, super::super::err::SevenZParserError<&[u8]>> =
+        parsers::resolve_external(&fi, &external);
+    assert_eq!(res.unwrap(), expected);
+
+    let res: Result<_, super::super::err::SevenZParserError<&[u8]>> =
+        parsers::resolve_external(&fi, &external[..1]);
+    assert_eq!(
+        res.unwrap_err().kind,
+        super::super::err::SevenZParserErrorKind::InvalidExternalStreamIndex(1)
+    );
+}
+
+#[test]
+fn serialize_roundtrip() {
+    use super::super::serialize;
+
+    let input = UNCOMPRESSED_ARCHIVE;
+    let (_, archive) = parsers::archive(input).unwrap();
+    let header = archive.header_or_packed_header.left().unwrap();
+    let packed_size = archive.signature_header.start_header.next_header_offset as usize;
+    let packed = &input[types::SIGNATURE_HEADER_SIZE_BYTES..][..packed_size];
+
+    // 7-Zip pads the header with kDummy properties, which aren't written back
+    let written = serialize::write_archive(packed, &header);
+    let (_, rewritten) = parsers::archive(&written).unwrap();
+    assert_eq!(rewritten.header_or_packed_header.left().unwrap(), header);
+    assert_eq!(
+        &written[types::SIGNATURE_HEADER_SIZE_BYTES..][..packed_size],
+        packed
+    );
+
+    for n in [0, 0x7F, 0x80, 0x3FFF, 0x4000, 0xFFFF_FFFF, u64::MAX] {
+        let mut out = vec![];
+        serialize::write_sevenz_uint64(&mut out, n);
+        assert_eq!(parsers::sevenz_uint64(&out).unwrap(), (&[][..], n));
+    }
+}
+
+#[test]
+fn streams_must_match_files() {
+    use super::super::serialize;
+
+    let input = UNCOMPRESSED_ARCHIVE;
+    let (_, archive) = parsers::archive(input).unwrap();
+    let header = archive.header_or_packed_header.left().unwrap();
+    let packed_size = archive.signature_header.start_header.next_header_offset as usize;
+    let packed = &input[types::SIGNATURE_HEADER_SIZE_BYTES..][..packed_size];
+    let parse_with_files = |num_files: usize, empty_stream: Option<BitVec>| {
+        let names = (0..num_files)
+            .map(|i| Right(widestring::U16String::from_str(&std::format!("{}.txt", i))))
+            .collect();
+        let mut properties = vec![types::FilesProperty::Names(names)];
+        if let Some(bits) = empty_stream {
+            properties.insert(0, types::FilesProperty::EmptyStream(bits));
+        }
+        let header = types::Header {
+            files: Some(types::FilesInfo {
+                num_files,
+                properties,
+            }),
+            ..header.clone()
+        };
+        let written = serialize::write_archive(packed, &header);
+        return super::super::parse(&written, crate::read::decode::decode_folder);
+    };
+
+    // A second file without a stream left for it
+    let res = parse_with_files(2, None);
+    assert_eq!(
+        res.unwrap_err().kind,
+        super::super::err::SevenZParserErrorKind::InvalidStreamsInfo
+    );
+
+    // The only file has an empty stream, leaving the archive's stream over
+    let res = parse_with_files(1, Some(bitvec![1]));
+    assert_eq!(
+        res.unwrap_err().kind,
+        super::super::err::SevenZParserErrorKind::InvalidStreamsInfo
+    );
+
+    assert!(parse_with_files(1, None).is_ok());
+}
+
+#[test]
+fn archive_properties() {
+    let input = [
+        0x02, 0x16, 0x02, b'a', 0x00, 0x19, 0x00, 0x42, 0x01, 0xCC, 0x00, 0xFF,
+    ];
+    let (rest, res) = parsers::archive_properties(&input).unwrap();
+    let expected = types::ArchiveProperties {
+        property_data: vec![
+            (types::PropertyID::Comment, vec![b'a', 0x00]),
+            (types::PropertyID::Dummy, vec![]),
+        ],
+        unknown_properties: vec![(0x42, vec![0xCC])],
+    };
+    assert_eq!(res, expected);
+    assert_eq!(rest, &[0xFF]);
+}
+
+#[test]
+fn unknown_properties_are_surfaced() {
+    let input = include_bytes!("../../../testdata/test-startpos.7z");
+    let ar = super::super::parse(input, crate::read::decode::decode_folder).unwrap();
+    assert_eq!(ar.unknown_file_properties, vec![(0x30, b"abc".to_vec())]);
+    assert_eq!(ar.unknown_properties, vec![]);
+
+    // FilesInfo, 1 file, NtSecure (2 bytes), End
+    let input: &[u8] = &[0x05, 0x01, 0x1A, 0x02, 0x01, 0x02, 0x00];
+    let (_, res) = parsers::files_info(input).unwrap();
+    assert_eq!(
+        res.properties,
+        vec![types::FilesProperty::Unknown(0x1A, vec![0x01, 0x02])]
+    );
+}
+
+#[test]
+fn region() {
+    let input: &[u8] = &[0, 1, 2, 3];
+    let res: Result<_, super::super::err::SevenZParserError<&[u8]>> = parsers::region(input, 1, 2);
+    assert_eq!(res.unwrap(), &[1, 2]);
+
+    let res: Result<_, super::super::err::SevenZParserError<&[u8]>> = parsers::region(input, 3, 2);
+    assert_eq!(
+        res.unwrap_err().kind,
+        super::super::err::SevenZParserErrorKind::Truncated
+    );
+
+    let res: Result<_, super::super::err::SevenZParserError<&[u8]>> =
+        parsers::region(input, u64::MAX, 2);
+    assert_eq!(
+        res.unwrap_err().kind,
+        super::super::err::SevenZParserErrorKind::OffsetOutOfRange(u64::MAX, 2)
+    );
+
+    // Header beyond the end of the archive
+    let mut data = UNCOMPRESSED_ARCHIVE.to_vec();
+    data.truncate(data.len() - 1);
+    assert!(matches!(
+        parsers::archive(&data),
+        Err(nom::Err::Failure(super::super::err::SevenZParserError {
+            kind: super::super::err::SevenZParserErrorKind::Truncated,
+            ..
+        }))
+    ));
+}
+
+#[test]
+fn files_info_huge_num_files() {
+    // FilesInfo with 2^60 files, all of which have attributes, but no data for them
+    let input: &[u8] = &[
+        0x05, 0xFF, 0, 0, 0, 0, 0, 0, 0, 0x10, 0x15, 0x02, 0x01, 0x00, 0x00,
+    ];
+    assert!(matches!(
+        parsers::files_info(input),
+        Err(nom::Err::Failure(super::super::err::SevenZParserError {
+            kind: super::super::err::SevenZParserErrorKind::Truncated,
+            ..
+        }))
+    ));
+
+    // Externally stored names aren't allocated for before their data is known
+    let input: &[u8] = &[
+        0x05, 0xFF, 0, 0, 0, 0, 0, 0, 0, 0x10, 0x11, 0x02, 0x01, 0x00, 0x00,
+    ];
+    let (_, res) = parsers::files_info(input).unwrap();
+    assert_eq!(
+        res.properties,
+        vec![types::FilesProperty::Names(vec![Left(0)])]
+    );
+    let res: Result<_, super::super::err::SevenZParserError<&[u8]>> =
+        parsers::resolve_external(&res, &[vec![0x61, 0x00, 0x00, 0x00]]);
+    assert!(res.is_err());
+}
+
+#[test]
+fn digests_huge_num_digests() {
+    // All 2^60 digests defined, but only one of them present
+    assert!(matches!(
+        parsers::digests(&[0x01, 0x00, 0x00, 0x00, 0x00], 1 << 60),
+        Err(nom::Err::Failure(super::super::err::SevenZParserError {
+            kind: super::super::err::SevenZParserErrorKind::Truncated,
+            ..
+        }))
+    ));
+}
+
+#[test]
+fn error_records_entry() {
+    use super::super::err::ErrorContext;
+
+    // Two files, but the second name is missing its terminator
+    let input: &[u8] = &[
+        0x05, 0x02, 0x11, 0x09, 0x00, 0x61, 0x00, 0x00, 0x00, 0x62, 0x00, 0x63, 0x00,
+    ];
+    let e = match parsers::files_info(input).unwrap_err() {
+        nom::Err::Error(e) | nom::Err::Failure(e) => e,
+        nom::Err::Incomplete(_) => unreachable!(),
+    };
+    let path: Vec<ErrorContext> = e.ctx.iter().rev().map(|(_, c)| *c).collect();
+    assert!(path.ends_with(&[
+        ErrorContext::Named("names names"),
+        ErrorContext::Entry(1),
+        ErrorContext::Named("wchar_str data"),
+        ErrorContext::Nom(nom::error::ErrorKind::ManyTill),
+    ]));
+    // The entry starts after the first name
+    let entry = e
+        .ctx
+        .iter()
+        .find(|(_, c)| *c == ErrorContext::Entry(1))
+        .unwrap();
+    assert_eq!(entry.0, &input[9..]);
+}
+
+#[test]
+fn packed_header() {
+    use super::super::crc::sevenz_crc;
+    use super::super::serialize;
+
+    let input = UNCOMPRESSED_ARCHIVE;
+    let (_, archive) = parsers::archive(input).unwrap();
+    let header = archive.header_or_packed_header.left().unwrap();
+    let packed_size = archive.signature_header.start_header.next_header_offset as usize;
+    let packed = &input[types::SIGNATURE_HEADER_SIZE_BYTES..][..packed_size];
+    let mut raw_header = vec![];
+    serialize::write_header(&mut raw_header, &header);
+
+    // Store the header after the contents using the Copy method
+    let write_packed = |header_crc: u32| {
+        let streams_info = types::StreamsInfo {
+            pack_info: Some(types::PackInfo {
+                pack_pos: packed.len() as u64,
+                num_pack_streams: 1,
+                sizes: Some(vec![raw_header.len() as u64]),
+                crcs: None,
+            }),
+            coders_info: Some(types::CodersInfo {
+                num_folders: 1,
+                folders_or_data_stream_index: Right(vec![types::Folder {
+                    coders: vec![types::Coder {
+                        complex: None,
+                        attrs: None,
+                        id: vec![0x00],
+                    }],
+                    bind_pairs: vec![],
+                    packed_streams_indices: None,
+                }]),
+                streams_unpack_sizes: vec![raw_header.len() as u64],
+                folders_unpack_digests: Some(vec![Some(header_crc)]),
+            }),
+            substreams_info: None,
+        };
+        let mut next_header = vec![types::PropertyID::EncodedHeader as u8];
+        serialize::write_streams_info(&mut next_header, &streams_info);
+        let mut start_header = vec![];
+        start_header.extend_from_slice(&((packed.len() + raw_header.len()) as u64).to_le_bytes());
+        start_header.extend_from_slice(&(next_header.len() as u64).to_le_bytes());
+        start_header.extend_from_slice(&sevenz_crc(&next_header).to_le_bytes());
+
+        let mut out = parsers::MAGIC.to_vec();
+        out.extend_from_slice(&[0, 4]);
+        out.extend_from_slice(&sevenz_crc(&start_header).to_le_bytes());
+        out.extend_from_slice(&start_header);
+        out.extend_from_slice(packed);
+        out.extend_from_slice(&raw_header);
+        out.extend_from_slice(&next_header);
+        return out;
+    };
+
+    let written = write_packed(sevenz_crc(&raw_header));
+    let (_, archive) = parsers::archive(&written).unwrap();
+    assert!(archive.header_or_packed_header.is_right());
+    let plain = super::super::parse(input, crate::read::decode::decode_folder).unwrap();
+    let ar = super::super::parse(&written, crate::read::decode::decode_folder).unwrap();
+    assert!(ar.info.encoded_header);
+    assert!(!plain.info.encoded_header);
+    assert_eq!(format!("{:?}", ar.files), format!("{:?}", plain.files));
+    assert_eq!(ar.folders, plain.folders);
+
+    let written = write_packed(!sevenz_crc(&raw_header));
+    let res = super::super::parse(&written, crate::read::decode::decode_folder);
+    assert!(matches!(
+        res.unwrap_err().kind,
+        super::super::err::SevenZParserErrorKind::Crc(..)
+    ));
+}
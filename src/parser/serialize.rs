@@ -0,0 +1,331 @@
+//! Serializers for the 7z format, the counterpart of the parsers.
+//!
+//! Each function appends the encoded structure to the output buffer.
+
+use super::crc::sevenz_crc;
+use super::parsers::MAGIC;
+use super::types::*;
+
+use alloc::vec::Vec;
+use bitvec::prelude::*;
+use either::*;
+use widestring::{U16Str, U16String};
+
+/// Encode a u64 in 7zip's packed integer format.
+pub fn write_sevenz_uint64(out: &mut Vec<u8>, val: u64) {
+    // Each leading one in the first byte announces another full byte following it
+    for num_bytes in 0..8 {
+        if val < (1 << (7 * (num_bytes + 1))) {
+            let mask = !(0xFFu8 >> num_bytes);
+            out.push(mask | (val >> (8 * num_bytes)) as u8);
+            out.extend_from_slice(&val.to_le_bytes()[..num_bytes]);
+            return;
+        }
+    }
+    out.push(0xFF);
+    out.extend_from_slice(&val.to_le_bytes());
+}
+
+/// Encode the bits MSB first, padding the last byte with zeroes.
+pub fn write_bits<I: IntoIterator<Item = bool>>(out: &mut Vec<u8>, bits: I) {
+    let mut byte = 0u8;
+    let mut num_bits = 0;
+    for bit in bits {
+        byte |= (bit as u8) << (7 - num_bits);
+        num_bits += 1;
+        if num_bits == 8 {
+            out.push(byte);
+            byte = 0;
+            num_bits = 0;
+        }
+    }
+    if num_bits > 0 {
+        out.push(byte);
+    }
+}
+
+/// Write the "all defined" byte, followed by the bit vector if not all are defined.
+fn write_defined<T>(out: &mut Vec<u8>, values: &[Option<T>]) {
+    if values.iter().all(|x| x.is_some()) {
+        out.push(1);
+    } else {
+        out.push(0);
+        write_bits(out, values.iter().map(|x| x.is_some()));
+    }
+}
+
+pub fn write_digests(out: &mut Vec<u8>, digests: &[Option<u32>]) {
+    write_defined(out, digests);
+    for crc in digests.iter().flatten() {
+        out.extend_from_slice(&crc.to_le_bytes());
+    }
+}
+
+pub fn write_pack_info(out: &mut Vec<u8>, pi: &PackInfo) {
+    out.push(PropertyID::PackInfo as u8);
+    write_sevenz_uint64(out, pi.pack_pos);
+    write_sevenz_uint64(out, pi.num_pack_streams as u64);
+    if let Some(sizes) = &pi.sizes {
+        out.push(PropertyID::Size as u8);
+        sizes.iter().for_each(|x| write_sevenz_uint64(out, *x));
+    }
+    if let Some(crcs) = &pi.crcs {
+        out.push(PropertyID::CRC as u8);
+        write_digests(out, crcs);
+    }
+    out.push(PropertyID::End as u8);
+}
+
+pub fn write_coder(out: &mut Vec<u8>, coder: &Coder) {
+    let mut props = coder.id.len() as u8;
+    if coder.complex.is_some() {
+        props |= 0b0001_0000;
+    }
+    if coder.attrs.is_some() {
+        props |= 0b0010_0000;
+    }
+    out.push(props);
+    out.extend_from_slice(&coder.id);
+    if let Some(c) = coder.complex {
+        write_sevenz_uint64(out, c.num_in_streams);
+        write_sevenz_uint64(out, c.num_out_streams);
+    }
+    if let Some(attrs) = &coder.attrs {
+        write_sevenz_uint64(out, attrs.len() as u64);
+        out.extend_from_slice(attrs);
+    }
+}
+
+pub fn write_folder(out: &mut Vec<u8>, folder: &Folder) {
+    write_sevenz_uint64(out, folder.coders.len() as u64);
+    folder.coders.iter().for_each(|x| write_coder(out, x));
+    for (in_index, out_index) in &folder.bind_pairs {
+        write_sevenz_uint64(out, *in_index);
+        write_sevenz_uint64(out, *out_index);
+    }
+    if let Some(indices) = &folder.packed_streams_indices {
+        indices.iter().for_each(|x| write_sevenz_uint64(out, *x));
+    }
+}
+
+pub fn write_coders_info(out: &mut Vec<u8>, ci: &CodersInfo) {
+    out.push(PropertyID::UnPackInfo as u8);
+    out.push(PropertyID::Folder as u8);
+    write_sevenz_uint64(out, ci.num_folders as u64);
+    match &ci.folders_or_data_stream_index {
+        Left(data_stream_index) => {
+            out.push(1);
+            write_sevenz_uint64(out, *data_stream_index);
+        }
+        Right(folders) => {
+            out.push(0);
+            folders.iter().for_each(|x| write_folder(out, x));
+        }
+    }
+    out.push(PropertyID::CodersUnPackSize as u8);
+    ci.streams_unpack_sizes
+        .iter()
+        .for_each(|x| write_sevenz_uint64(out, *x));
+    if let Some(digests) = &ci.folders_unpack_digests {
+        out.push(PropertyID::CRC as u8);
+        write_digests(out, digests);
+    }
+    out.push(PropertyID::End as u8);
+}
+
+pub fn write_substreams_info(out: &mut Vec<u8>, ssi: &SubStreamsInfo) {
+    out.push(PropertyID::SubStreamsInfo as u8);
+    if let Some(nums) = &ssi.num_unpack_streams_in_folders {
+        out.push(PropertyID::NumUnPackStream as u8);
+        nums.iter().for_each(|x| write_sevenz_uint64(out, *x));
+    }
+    if let Some(sizes) = &ssi.unpack_sizes {
+        out.push(PropertyID::Size as u8);
+        sizes.iter().for_each(|x| write_sevenz_uint64(out, *x));
+    }
+    if let Some(digests) = &ssi.unknown_digests {
+        out.push(PropertyID::CRC as u8);
+        write_digests(out, digests);
+    }
+    out.push(PropertyID::End as u8);
+}
+
+pub fn write_streams_info(out: &mut Vec<u8>, si: &StreamsInfo) {
+    if let Some(pi) = &si.pack_info {
+        write_pack_info(out, pi);
+    }
+    if let Some(ci) = &si.coders_info {
+        write_coders_info(out, ci);
+    }
+    if let Some(ssi) = &si.substreams_info {
+        write_substreams_info(out, ssi);
+    }
+    out.push(PropertyID::End as u8);
+}
+
+/// Write a property of the files info, prefixed by it's ID and size.
+fn write_files_property<I: Into<u8>>(out: &mut Vec<u8>, id: I, data: &[u8]) {
+    out.push(id.into());
+    write_sevenz_uint64(out, data.len() as u64);
+    out.extend_from_slice(data);
+}
+
+/// Write the values of a property which may be defined for only some files, and may be stored externally.
+fn write_optional_values<T>(
+    values: &[Option<Either<u64, T>>],
+    write_value: impl Fn(&mut Vec<u8>, &T),
+) -> Vec<u8> {
+    let mut data = Vec::new();
+    write_defined(&mut data, values);
+    let external = values.iter().flatten().find_map(|x| x.as_ref().left());
+    match external {
+        Some(data_index) => {
+            data.push(1);
+            write_sevenz_uint64(&mut data, *data_index);
+        }
+        None => {
+            data.push(0);
+            for value in values.iter().flatten() {
+                if let Right(v) = value {
+                    write_value(&mut data, v);
+                }
+            }
+        }
+    }
+    return data;
+}
+
+/// Write a list of strings, or the index of the external data they're stored in.
+fn write_strings(strings: &[Either<u64, U16String>]) -> Vec<u8> {
+    let mut data = Vec::new();
+    match strings.iter().find_map(|x| x.as_ref().left()) {
+        Some(data_index) => {
+            data.push(1);
+            write_sevenz_uint64(&mut data, *data_index);
+        }
+        None => {
+            data.push(0);
+            for string in strings.iter().filter_map(|x| x.as_ref().right()) {
+                write_wchar_str(&mut data, string);
+            }
+        }
+    }
+    return data;
+}
+
+/// Write a null-terminated string of UTF-16LE code units.
+pub fn write_wchar_str(out: &mut Vec<u8>, string: &U16Str) {
+    string
+        .as_slice()
+        .iter()
+        .chain(&[0])
+        .for_each(|c| out.extend_from_slice(&c.to_le_bytes()));
+}
+
+fn write_bits_property(out: &mut Vec<u8>, id: PropertyID, bits: &BitVec) {
+    let mut data = Vec::new();
+    write_bits(&mut data, bits.iter().by_vals());
+    write_files_property(out, id, &data);
+}
+
+pub fn write_files_info(out: &mut Vec<u8>, fi: &FilesInfo) {
+    out.push(PropertyID::FilesInfo as u8);
+    write_sevenz_uint64(out, fi.num_files as u64);
+    for property in &fi.properties {
+        match property {
+            FilesProperty::EmptyStream(bits) => {
+                write_bits_property(out, PropertyID::EmptyStream, bits)
+            }
+            FilesProperty::EmptyFile(bits) => write_bits_property(out, PropertyID::EmptyFile, bits),
+            FilesProperty::Anti(bits) => write_bits_property(out, PropertyID::Anti, bits),
+            FilesProperty::CTime(times)
+            | FilesProperty::ATime(times)
+            | FilesProperty::MTime(times) => {
+                let id = match property {
+                    FilesProperty::CTime(_) => PropertyID::CTime,
+                    FilesProperty::ATime(_) => PropertyID::ATime,
+                    _ => PropertyID::MTime,
+                };
+                let data = write_optional_values(times, |out, t: &u64| {
+                    out.extend_from_slice(&t.to_le_bytes())
+                });
+                write_files_property(out, id, &data);
+            }
+            FilesProperty::Names(names) => {
+                write_files_property(out, PropertyID::Name, &write_strings(names))
+            }
+            FilesProperty::Comments(comments) => {
+                let data =
+                    write_optional_values(comments, |out, c: &U16String| write_wchar_str(out, c));
+                write_files_property(out, PropertyID::Comment, &data);
+            }
+            FilesProperty::StartPos(positions) => {
+                let data = write_optional_values(positions, |out, p: &u64| {
+                    out.extend_from_slice(&p.to_le_bytes())
+                });
+                write_files_property(out, PropertyID::StartPos, &data);
+            }
+            FilesProperty::Unknown(id, data) => write_files_property(out, *id, data),
+            FilesProperty::Attributes(attrs) => {
+                let data = write_optional_values(attrs, |out, a: &u32| {
+                    out.extend_from_slice(&a.to_le_bytes())
+                });
+                write_files_property(out, PropertyID::WinAttributes, &data);
+            }
+        }
+    }
+    out.push(PropertyID::End as u8);
+}
+
+pub fn write_archive_properties(out: &mut Vec<u8>, ap: &ArchiveProperties) {
+    out.push(PropertyID::ArchiveProperties as u8);
+    for (id, data) in &ap.property_data {
+        write_files_property(out, *id as u8, data);
+    }
+    for (id, data) in &ap.unknown_properties {
+        write_files_property(out, *id, data);
+    }
+    out.push(PropertyID::End as u8);
+}
+
+pub fn write_header(out: &mut Vec<u8>, header: &Header) {
+    out.push(PropertyID::Header as u8);
+    if let Some(ap) = &header.archive_properties {
+        write_archive_properties(out, ap);
+    }
+    if let Some(si) = &header.additional_streams {
+        out.push(PropertyID::AdditionalStreamsInfo as u8);
+        write_streams_info(out, si);
+    }
+    if let Some(si) = &header.main_streams {
+        out.push(PropertyID::MainStreamsInfo as u8);
+        write_streams_info(out, si);
+    }
+    if let Some(fi) = &header.files {
+        write_files_info(out, fi);
+    }
+    out.push(PropertyID::End as u8);
+}
+
+/// Assemble an entire archive from the packed streams and the header describing them.
+///
+/// The header is placed directly after the packed streams, so it's pack info must start at position 0.
+pub fn write_archive(packed_streams: &[u8], header: &Header) -> Vec<u8> {
+    let mut raw_header = Vec::new();
+    write_header(&mut raw_header, header);
+
+    let mut start_header = Vec::with_capacity(START_HEADER_SIZE_BYTES);
+    start_header.extend_from_slice(&(packed_streams.len() as u64).to_le_bytes());
+    start_header.extend_from_slice(&(raw_header.len() as u64).to_le_bytes());
+    start_header.extend_from_slice(&sevenz_crc(&raw_header).to_le_bytes());
+
+    let mut out =
+        Vec::with_capacity(SIGNATURE_HEADER_SIZE_BYTES + packed_streams.len() + raw_header.len());
+    out.extend_from_slice(&MAGIC);
+    out.extend_from_slice(&[0, 4]);
+    out.extend_from_slice(&sevenz_crc(&start_header).to_le_bytes());
+    out.extend_from_slice(&start_header);
+    out.extend_from_slice(packed_streams);
+    out.extend_from_slice(&raw_header);
+    return out;
+}
@@ -1,5 +1,7 @@
 use core::convert::TryInto;
 
+use crate::codec::Method;
+
 use super::*;
 
 #[derive(Debug, Clone, Copy, PartialEq)]
@@ -16,9 +18,14 @@ pub struct Coder {
 }
 
 impl Coder {
+    /// Describe the method this coder uses.
+    pub fn method(&self) -> Method {
+        return Method::from_coder(&self.id, self.attrs.as_deref());
+    }
+
     pub fn get_num_out_streams(&self) -> usize {
         return match self.complex {
-            Some(n) => n.num_out_streams.try_into().unwrap(),
+            Some(n) => n.num_out_streams.try_into().unwrap_or(usize::MAX),
             None => 1,
         };
     }
@@ -35,5 +42,5 @@ pub struct CodersInfo {
     pub num_folders: usize,
     pub folders_or_data_stream_index: Either<u64, Vec<Folder>>,
     pub streams_unpack_sizes: Vec<u64>,
-    pub folders_unpack_digests: Option<Vec<u32>>,
+    pub folders_unpack_digests: Option<Vec<Option<u32>>>,
 }
@@ -3,8 +3,13 @@ use super::*;
 /// Left: external data index, right: time
 pub type FileTime = Either<u64, u64>;
 
-/// Left: external data index, right: name
-pub type FileName = Either<u64, String>;
+/// Left: external data index, right: name as stored in the archive (UTF-16, but not necessarily valid)
+///
+/// Externally stored names are a single `Left` until they're resolved.
+pub type FileName = Either<u64, U16String>;
+
+/// Left: external data index, right: comment as stored in the archive
+pub type FileComment = Either<u64, U16String>;
 
 /// Left: External data index, right: attrs
 pub type FileAttr = Either<u64, u32>;
@@ -18,7 +23,12 @@ pub enum FilesProperty {
     ATime(Vec<Option<FileTime>>),
     MTime(Vec<Option<FileTime>>),
     Names(Vec<FileName>),
+    Comments(Vec<Option<FileComment>>),
     Attributes(Vec<Option<FileAttr>>),
+    /// Position of the file's data within the original file, for archives created from parts of files.
+    StartPos(Vec<Option<Either<u64, u64>>>),
+    /// A property whose contents this parser doesn't understand, as raw ID and data.
+    Unknown(u8, Vec<u8>),
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -28,22 +38,13 @@ pub struct FilesInfo {
 }
 
 impl FilesInfo {
+    /// Retrieve the first property of the same kind as `kind`, ignoring it's contents.
     pub fn get_property(&self, kind: FilesProperty) -> Option<FilesProperty> {
-        let props: Vec<FilesProperty> = self
+        let kind = core::mem::discriminant(&kind);
+        return self
             .properties
             .iter()
-            .map(|x| x.clone())
-            .filter(|x| match x {
-                kind => true,
-                _ => false,
-            })
-            .take(1)
-            .collect();
-        let prop = if props.len() > 0 {
-            Some(props[0].clone())
-        } else {
-            None
-        };
-        return prop;
+            .find(|x| core::mem::discriminant(*x) == kind)
+            .cloned();
     }
 }
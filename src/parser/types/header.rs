@@ -1,6 +1,8 @@
 use super::*;
 
 pub const START_HEADER_SIZE_BYTES: usize = 8 + 8 + 4;
+/// Magic bytes, version, start header CRC and the start header itself.
+pub const SIGNATURE_HEADER_SIZE_BYTES: usize = 6 + 2 + 4 + START_HEADER_SIZE_BYTES;
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct StartHeader {
@@ -30,8 +32,12 @@ pub struct Header {
     pub files: Option<FilesInfo>,
 }
 
+/// A header which is stored packed, like the files' contents.
 #[derive(Debug, Clone, PartialEq)]
-pub struct PackedHeader {}
+pub struct PackedHeader {
+    /// Describes the single folder the actual header is stored in.
+    pub streams_info: StreamsInfo,
+}
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct HeaderInfo {}
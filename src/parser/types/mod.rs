@@ -13,32 +13,26 @@ pub use header::*;
 pub use property_id::*;
 pub use streams_info::*;
 
-use alloc::string::String;
 use alloc::vec::Vec;
 use bitvec::prelude::*;
 use either::Either;
+use widestring::U16String;
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct PackInfo {
     pub pack_pos: u64,
     pub num_pack_streams: usize,
     pub sizes: Option<Vec<u64>>,
-    pub crcs: Option<Vec<u32>>,
+    pub crcs: Option<Vec<Option<u32>>>,
 }
 
-#[derive(Debug, Clone, PartialEq)]
-pub struct PackedStreams {}
-#[derive(Debug, Clone, PartialEq)]
-pub struct PackedStreamsForHeaders {}
-
-#[derive(Debug, Clone, PartialEq)]
-pub struct Packed {}
-
 #[derive(Debug, Clone, PartialEq)]
 pub struct ArchiveProperties {
     // Would be nice to have property data also be 0-copy, but that'd
     // lead to some messy lifetimes.
     pub property_data: Vec<(PropertyID, Vec<u8>)>,
+    /// Properties with IDs unknown to this parser, as raw ID and data.
+    pub unknown_properties: Vec<(u8, Vec<u8>)>,
 }
 /// The top-level archive structure.
 #[derive(Debug, Clone, PartialEq)]
@@ -1,6 +1,8 @@
 use core::convert::TryFrom;
 
+/// Names follow those used in 7zFormat.txt.
 #[repr(u8)]
+#[allow(clippy::upper_case_acronyms)]
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum PropertyID {
     End = 0x00,
@@ -29,6 +31,16 @@ pub enum PropertyID {
     EncodedHeader = 0x17,
     StartPos = 0x18,
     Dummy = 0x19,
+    // The following are reserved by 7-Zip's source, but their contents aren't documented.
+    NtSecure = 0x1A,
+    Parent = 0x1B,
+    IsAux = 0x1C,
+}
+
+impl From<PropertyID> for u8 {
+    fn from(id: PropertyID) -> u8 {
+        return id as u8;
+    }
 }
 
 impl TryFrom<u8> for PropertyID {
@@ -62,6 +74,9 @@ impl TryFrom<u8> for PropertyID {
             0x17 => Ok(EncodedHeader),
             0x18 => Ok(StartPos),
             0x19 => Ok(Dummy),
+            0x1A => Ok(NtSecure),
+            0x1B => Ok(Parent),
+            0x1C => Ok(IsAux),
             _ => Err(()),
         }
     }
@@ -4,7 +4,7 @@ use super::*;
 pub struct SubStreamsInfo {
     pub num_unpack_streams_in_folders: Option<Vec<u64>>,
     pub unpack_sizes: Option<Vec<u64>>,
-    pub unknown_digests: Option<Vec<u32>>,
+    pub unknown_digests: Option<Vec<Option<u32>>>,
 }
 
 #[derive(Debug, Clone, PartialEq)]
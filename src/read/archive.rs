@@ -0,0 +1,131 @@
+//! This module implements a handle for an archive which is parsed once and then used to look up many files.
+
+use super::err::Error;
+use super::path::glob_match;
+use super::{ArchiveIterator, ContentsIterator, FileHandle, InvalidNamePolicy, ReadOptions};
+
+use super::decode::decode_folder;
+use crate::parser::{parse_with_options, ArchiveInfo, HighLevelArchive};
+
+use alloc::collections::BTreeMap;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+/// A parsed archive, for retrieving files by name, index or glob pattern.
+///
+/// Unlike the simplistic API, the archive's headers are only parsed once,
+/// and extracting a file only decodes the folder containing it.
+#[derive(Debug, Clone)]
+pub struct Archive<'a> {
+    ar: HighLevelArchive,
+    /// The entire archive's unprocessed data.
+    archive_raw: &'a [u8],
+    /// Index of the first file with each name, as returned by `FileHandle::name()`.
+    names: BTreeMap<String, usize>,
+}
+
+impl<'a> Archive<'a> {
+    /// Parse the archive contained in `archive_data`.
+    pub fn new(archive_data: &'a [u8]) -> Result<Archive<'a>, Error> {
+        return Archive::with_options(archive_data, &ReadOptions::default());
+    }
+
+    /// Like `Archive::new()`, but with the given codecs, limits and recovery mode.
+    pub fn with_options(
+        archive_data: &'a [u8],
+        options: &ReadOptions,
+    ) -> Result<Archive<'a>, Error> {
+        let ar = parse_with_options(archive_data, options, decode_folder)?;
+        let mut names = BTreeMap::new();
+        for (i, file) in ar.files.iter().enumerate() {
+            // Can't fail with this policy
+            let name = super::name::to_string(&file.name, InvalidNamePolicy::Lossy).unwrap();
+            names.entry(name).or_insert(i);
+        }
+        return Ok(Archive {
+            ar,
+            archive_raw: archive_data,
+            names,
+        });
+    }
+
+    /// Get information about the archive as a whole.
+    pub fn info(&self) -> &ArchiveInfo {
+        return &self.ar.info;
+    }
+
+    /// Get the archive's comment, if it's got one.
+    ///
+    /// Invalid UTF-16 in the comment is replaced by U+FFFD REPLACEMENT CHARACTER.
+    pub fn comment(&self) -> Option<String> {
+        return self.ar.comment.as_ref().map(|c| c.to_string_lossy());
+    }
+
+    /// Get the number of files in the archive.
+    pub fn len(&self) -> usize {
+        return self.ar.files.len();
+    }
+
+    /// Whether the archive contains no files.
+    pub fn is_empty(&self) -> bool {
+        return self.ar.files.is_empty();
+    }
+
+    /// Iterate over all files in the archive, in the order they're stored.
+    pub fn iter(&self) -> ArchiveIterator<'_> {
+        return ArchiveIterator::new(&self.ar, self.archive_raw);
+    }
+
+    /// Iterate over all files in the archive together with their contents, in the order they're stored.
+    ///
+    /// Unlike calling `FileHandle::extract_contents_vec()` for each file, this decodes each folder once,
+    /// so extracting a solid archive takes linear time.
+    pub fn contents(&self) -> ContentsIterator<'_> {
+        return ContentsIterator::new(self.iter(), 0);
+    }
+
+    /// Get the file at `index`, in the order they're stored.
+    pub fn by_index(&self, index: usize) -> Option<FileHandle<'a>> {
+        if index >= self.ar.files.len() {
+            return None;
+        }
+        return Some(FileHandle::new(&self.ar, self.archive_raw, index));
+    }
+
+    /// Get the index of the file with the given `name`.
+    ///
+    /// If several files share the name, the first one is returned.
+    pub fn index_of(&self, name: &str) -> Option<usize> {
+        return self.names.get(name).copied();
+    }
+
+    /// Get the file with the given `name`, see `Archive::index_of()`.
+    pub fn by_name(&self, name: &str) -> Option<FileHandle<'a>> {
+        return self.index_of(name).and_then(|i| self.by_index(i));
+    }
+
+    /// Get all files whose name matches the glob `pattern`, in the order they're stored.
+    ///
+    /// See `glob_match()` for the supported syntax.
+    pub fn glob(&self, pattern: &str) -> Vec<FileHandle<'a>> {
+        return (0..self.ar.files.len())
+            .filter(|&i| {
+                let name = &self.ar.files[i].name;
+                // Can't fail with this policy
+                let name = super::name::to_string(name, InvalidNamePolicy::Lossy).unwrap();
+                glob_match(pattern, &name)
+            })
+            .map(|i| FileHandle::new(&self.ar, self.archive_raw, i))
+            .collect();
+    }
+
+    /// Extract the contents of the file with the given `name` into a vector.
+    ///
+    /// See `FileHandle::extract_contents_vec()` for caveats.
+    pub fn extract(&self, name: &str) -> Result<Vec<u8>, Error> {
+        return match self.by_name(name) {
+            Some(file) => file.extract_contents_vec(),
+            None => Err(Error::NoSuchFileName(String::from(name))),
+        };
+    }
+}
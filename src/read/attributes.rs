@@ -0,0 +1,142 @@
+//! This module implements decoding of the attributes stored for each file.
+//!
+//! 7zip stores Windows file attributes, while p7zip additionally stores the Unix mode
+//! in the high 16 bits when the `FILE_ATTRIBUTE_UNIX_EXTENSION` flag is set.
+
+const FILE_ATTRIBUTE_READONLY: u32 = 0x0001;
+const FILE_ATTRIBUTE_HIDDEN: u32 = 0x0002;
+const FILE_ATTRIBUTE_SYSTEM: u32 = 0x0004;
+const FILE_ATTRIBUTE_DIRECTORY: u32 = 0x0010;
+const FILE_ATTRIBUTE_ARCHIVE: u32 = 0x0020;
+const FILE_ATTRIBUTE_REPARSE_POINT: u32 = 0x0400;
+/// Set by p7zip if the high 16 bits contain a Unix mode.
+const FILE_ATTRIBUTE_UNIX_EXTENSION: u32 = 0x8000;
+
+const S_IFMT: u32 = 0o170000;
+const S_IFSOCK: u32 = 0o140000;
+const S_IFLNK: u32 = 0o120000;
+const S_IFREG: u32 = 0o100000;
+const S_IFBLK: u32 = 0o060000;
+const S_IFDIR: u32 = 0o040000;
+const S_IFCHR: u32 = 0o020000;
+const S_IFIFO: u32 = 0o010000;
+
+/// The type of a file, as recorded in it's Unix mode.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UnixFileType {
+    Regular,
+    Directory,
+    Symlink,
+    CharDevice,
+    BlockDevice,
+    Fifo,
+    Socket,
+}
+
+/// The attributes of a file contained within an archive.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Attributes {
+    raw: u32,
+}
+
+impl Attributes {
+    /// Create attributes from their raw representation in the archive.
+    pub fn new(raw: u32) -> Attributes {
+        return Attributes { raw };
+    }
+
+    /// The raw attribute value, as stored in the archive.
+    pub fn raw(&self) -> u32 {
+        return self.raw;
+    }
+
+    pub fn is_read_only(&self) -> bool {
+        return self.raw & FILE_ATTRIBUTE_READONLY != 0;
+    }
+
+    pub fn is_hidden(&self) -> bool {
+        return self.raw & FILE_ATTRIBUTE_HIDDEN != 0;
+    }
+
+    pub fn is_system(&self) -> bool {
+        return self.raw & FILE_ATTRIBUTE_SYSTEM != 0;
+    }
+
+    pub fn is_directory(&self) -> bool {
+        return self.raw & FILE_ATTRIBUTE_DIRECTORY != 0;
+    }
+
+    pub fn is_archive(&self) -> bool {
+        return self.raw & FILE_ATTRIBUTE_ARCHIVE != 0;
+    }
+
+    pub fn is_reparse_point(&self) -> bool {
+        return self.raw & FILE_ATTRIBUTE_REPARSE_POINT != 0;
+    }
+
+    /// Whether the high 16 bits carry a Unix mode.
+    pub fn has_unix_extension(&self) -> bool {
+        return self.raw & FILE_ATTRIBUTE_UNIX_EXTENSION != 0;
+    }
+
+    /// The full Unix `st_mode` (file type and permissions), if present.
+    pub fn unix_st_mode(&self) -> Option<u32> {
+        if !self.has_unix_extension() {
+            return None;
+        }
+        return Some(self.raw >> 16);
+    }
+
+    /// The Unix permission bits (including setuid, setgid and sticky), if present.
+    pub fn unix_mode(&self) -> Option<u32> {
+        return self.unix_st_mode().map(|m| m & 0o7777);
+    }
+
+    /// The Unix file type, if present and known.
+    pub fn unix_file_type(&self) -> Option<UnixFileType> {
+        let file_type = match self.unix_st_mode()? & S_IFMT {
+            S_IFSOCK => UnixFileType::Socket,
+            S_IFLNK => UnixFileType::Symlink,
+            S_IFREG => UnixFileType::Regular,
+            S_IFBLK => UnixFileType::BlockDevice,
+            S_IFDIR => UnixFileType::Directory,
+            S_IFCHR => UnixFileType::CharDevice,
+            S_IFIFO => UnixFileType::Fifo,
+            _ => return None,
+        };
+        return Some(file_type);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn windows_attributes() {
+        let attrs = Attributes::new(0x0413);
+        assert!(attrs.is_read_only());
+        assert!(attrs.is_hidden());
+        assert!(!attrs.is_system());
+        assert!(attrs.is_directory());
+        assert!(!attrs.is_archive());
+        assert!(attrs.is_reparse_point());
+        assert_eq!(attrs.unix_mode(), None);
+        assert_eq!(attrs.unix_file_type(), None);
+    }
+
+    #[test]
+    fn unix_extension() {
+        // As written by p7zip for a regular file with mode 0644
+        let attrs = Attributes::new(0x81A4_8020);
+        assert!(attrs.is_archive());
+        assert!(!attrs.is_directory());
+        assert_eq!(attrs.unix_st_mode(), Some(0o100644));
+        assert_eq!(attrs.unix_mode(), Some(0o644));
+        assert_eq!(attrs.unix_file_type(), Some(UnixFileType::Regular));
+
+        let attrs = Attributes::new((0o120777 << 16) | 0x8020);
+        assert_eq!(attrs.unix_mode(), Some(0o777));
+        assert_eq!(attrs.unix_file_type(), Some(UnixFileType::Symlink));
+    }
+}
@@ -0,0 +1,125 @@
+//! This module implements keeping decoded folders around, so that files sharing one can be extracted without decoding it again.
+
+use super::decode;
+use super::err::Error;
+use super::ReadOptions;
+
+use crate::parser::FolderInfo;
+
+use alloc::collections::BTreeMap;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+
+/// A decoded folder, or why it couldn't be decoded.
+#[derive(Debug, Clone)]
+struct CachedFolder {
+    data: Result<Arc<Vec<u8>>, Error>,
+    /// When the folder was last used, see `FolderCache::clock`.
+    used: u64,
+}
+
+impl CachedFolder {
+    fn size(&self) -> usize {
+        return self.data.as_ref().map(|d| d.len()).unwrap_or(0);
+    }
+}
+
+/// Decoded folders of a single archive, for extracting several files from them.
+///
+/// A folder always has to be decoded from it's start, so in solid archives extracting a file
+/// means decoding every file stored before it.
+/// Codecs can only decode entire folders, so whole folders are cached rather than the prefixes up to each file.
+///
+/// The most recently used folder is always kept, so extracting files in the order they're stored
+/// decodes each folder once, whatever the cache's size.
+/// `max_size` only bounds the other folders, which are kept until their decoded size
+/// together with the most recent one adds up to more than `max_size` bytes,
+/// dropping the least recently used ones first.
+///
+/// Folders which failed to decode are kept as well, so that the error is returned without decoding them again.
+/// If the cache is used with a different archive, everything cached from the previous one is dropped.
+#[derive(Debug, Clone)]
+pub struct FolderCache<'a> {
+    max_size: usize,
+    /// The archive the cached folders belong to.
+    archive_raw: Option<&'a [u8]>,
+    /// Cached folders by their index.
+    folders: BTreeMap<usize, CachedFolder>,
+    /// Index of each cached folder by when it was last used.
+    lru: BTreeMap<u64, usize>,
+    /// Total size of the cached folders.
+    size: usize,
+    /// Incremented whenever a folder is used.
+    clock: u64,
+}
+
+impl<'a> FolderCache<'a> {
+    /// Create an empty cache, keeping up to `max_size` bytes of decoded folders as described for `FolderCache`.
+    pub fn new(max_size: usize) -> FolderCache<'a> {
+        return FolderCache {
+            max_size,
+            archive_raw: None,
+            folders: BTreeMap::new(),
+            lru: BTreeMap::new(),
+            size: 0,
+            clock: 0,
+        };
+    }
+
+    /// Get the total size of the decoded folders currently kept.
+    pub fn size(&self) -> usize {
+        return self.size;
+    }
+
+    /// Drop all cached folders.
+    pub fn clear(&mut self) {
+        self.folders.clear();
+        self.lru.clear();
+        self.size = 0;
+    }
+
+    /// Get the folder at `index` of the archive `archive_raw`, decoding it unless it's cached.
+    pub(crate) fn get(
+        &mut self,
+        archive_raw: &'a [u8],
+        index: usize,
+        folder: &FolderInfo,
+        options: &ReadOptions,
+    ) -> Result<Arc<Vec<u8>>, Error> {
+        if !self
+            .archive_raw
+            .is_some_and(|raw| core::ptr::eq(raw, archive_raw))
+        {
+            self.clear();
+            self.archive_raw = Some(archive_raw);
+        }
+        self.clock += 1;
+
+        if let Some(cached) = self.folders.get_mut(&index) {
+            self.lru.remove(&cached.used);
+            self.lru.insert(self.clock, index);
+            cached.used = self.clock;
+            return cached.data.clone();
+        }
+
+        let data = decode::decode_folder(archive_raw, folder, options)
+            .map(Arc::new)
+            .map_err(Error::from);
+        let cached = CachedFolder {
+            data: data.clone(),
+            used: self.clock,
+        };
+        self.size += cached.size();
+        self.folders.insert(index, cached);
+        self.lru.insert(self.clock, index);
+
+        // The folder just decoded is the newest, so it's never dropped
+        while self.size > self.max_size && self.lru.len() > 1 {
+            let (_, oldest) = self.lru.pop_first().unwrap();
+            if let Some(dropped) = self.folders.remove(&oldest) {
+                self.size -= dropped.size();
+            }
+        }
+        return data;
+    }
+}
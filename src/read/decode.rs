@@ -1,24 +1,65 @@
-//! This module implements dispatching file contents to decoders.
+//! This module implements dispatching folder contents to decoders.
 
 use alloc::vec::Vec;
-use core::convert::TryFrom;
+use core::convert::{TryFrom, TryInto};
 
 use crate::codec::*;
-use crate::parser::FileStreamInfo;
+use crate::options::{Limit, Limits, ReadOptions};
+use crate::parser::FolderInfo;
 
-/// Handle dispatching data to the appropriate decoder to extract the file.
+/// Handle dispatching the folder's packed data to the appropriate decoder.
 ///
-/// Returns a fully decoded byte vector.
-pub fn decode_file<'a>(data: &[u8], stream: &FileStreamInfo) -> Result<Vec<u8>, CodecError> {
-    if stream.coder.complex.is_some() {
-        panic!("Decoding using complex coders not yet supported!");
+/// Returns a fully decoded byte vector containing the data of all files in the folder.
+///
+/// The decoder is looked up in `options.registry` by the coder's method ID.
+/// Decoding fails unless it produces exactly the folder's declared unpack size.
+pub fn decode_folder(
+    archive_raw: &[u8],
+    folder: &FolderInfo,
+    options: &ReadOptions,
+) -> Result<Vec<u8>, CodecError> {
+    if folder.coders.len() != 1 || folder.coders[0].complex.is_some() {
+        return Err(CodecError::Unsupported("complex coders"));
     }
+    let coder = &folder.coders[0];
+
+    let packed = folder
+        .packed_streams
+        .first()
+        .ok_or(CodecError::InvalidData)?;
+    let offset: usize = packed
+        .offset
+        .try_into()
+        .map_err(|_| CodecError::InvalidData)?;
+    let size: usize = packed
+        .size
+        .try_into()
+        .map_err(|_| CodecError::InvalidData)?;
+    let data = offset
+        .checked_add(size)
+        .and_then(|end| archive_raw.get(offset..end))
+        .ok_or(CodecError::InvalidData)?;
+
+    let dict_size = match coder.method() {
+        Method::Lzma(Some(p)) => p.dict_size,
+        Method::Lzma2(Some(p)) => p.dict_size,
+        Method::Ppmd(Some(p)) => p.mem_size,
+        _ => 0,
+    };
+    Limits::check(
+        dict_size as u64,
+        options.limits.max_dictionary_size,
+        Limit::DictionarySize,
+    )
+    .map_err(CodecError::LimitExceeded)?;
 
-    let codec_kind = Codecs::try_from(stream.coder.id.clone())?;
-    match codec_kind {
-        Codecs::Copy => {
-            let copy_codec = Copy::new();
-            return Ok(copy_codec.decode(data));
-        }
+    let codec = options
+        .registry
+        .decoder(&coder.id, coder.attrs.as_deref())?;
+    let max_size = usize::try_from(folder.unpack_size).unwrap_or(usize::MAX);
+    let res = codec.decode_bounded(data, max_size)?;
+    if res.len() != max_size {
+        return Err(CodecError::InvalidData);
     }
+    return Ok(res);
 }
@@ -1,30 +1,105 @@
 //! This module provides the top-level error type for this crate.
 
+use super::Limit;
 use crate::codec::CodecError;
-use crate::parser::SevenZParserError;
+use crate::parser::{SevenZParserError, SevenZParserErrorKind};
 
 use alloc::string::String;
 use core::convert::From;
+use core::fmt;
+use widestring::U16String;
 
 /// The top-level error type for this crate.
 ///
-/// The underlying data buffer must live at least as long as the error,
-/// because the parser errors contain subslices where a parsing issue occurred.
-#[derive(Debug, Clone)]
-pub enum Error<'a> {
-    Parser(SevenZParserError<&'a [u8]>),
+/// It doesn't borrow from the archive data, parser errors record offsets into it instead.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Error {
+    /// The archive's structure could not be parsed.
+    Parser(SevenZParserError<u64>),
     NoSuchFileName(String),
+    /// The file name is not valid UTF-16, and the chosen `InvalidNamePolicy` doesn't allow that.
+    InvalidFileName(U16String),
     CodecFailed(CodecError),
+    // Crc(expected, got), for extracted data which doesn't match it's CRC
+    Crc(u32, u32),
+    /// The entry is flagged as a symbolic link, but it's target can't be decoded.
+    InvalidSymlink,
+    /// The entry is not a symbolic link.
+    NotASymlink,
+    /// The symbolic link's target is absolute or leads outside of the destination directory.
+    UnsafeSymlinkTarget(String),
+    /// Reading the archive would exceed one of the configured limits.
+    LimitExceeded(Limit),
+    /// Interacting with the filesystem failed.
+    #[cfg(feature = "std")]
+    Io(std::io::ErrorKind),
 }
 
-impl<'a> From<SevenZParserError<&'a [u8]>> for Error<'a> {
-    fn from(e: SevenZParserError<&'a [u8]>) -> Self {
-        return Error::Parser(e);
+impl From<SevenZParserError<u64>> for Error {
+    fn from(e: SevenZParserError<u64>) -> Self {
+        return match e.kind {
+            SevenZParserErrorKind::LimitExceeded(limit)
+            | SevenZParserErrorKind::DecodingFailed(CodecError::LimitExceeded(limit)) => {
+                Error::LimitExceeded(limit)
+            }
+            _ => Error::Parser(e),
+        };
     }
 }
 
-impl<'a> From<CodecError> for Error<'a> {
+impl From<CodecError> for Error {
     fn from(e: CodecError) -> Self {
-        return Error::CodecFailed(e);
+        return match e {
+            CodecError::LimitExceeded(limit) => Error::LimitExceeded(limit),
+            _ => Error::CodecFailed(e),
+        };
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<std::io::Error> for Error {
+    fn from(e: std::io::Error) -> Self {
+        return Error::Io(e.kind());
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return match self {
+            Error::Parser(e) => write!(f, "invalid archive: {}", e),
+            Error::NoSuchFileName(name) => write!(f, "no file named {:?} in the archive", name),
+            Error::InvalidFileName(name) => {
+                write!(
+                    f,
+                    "file name {:?} is not valid UTF-16",
+                    name.to_string_lossy()
+                )
+            }
+            Error::CodecFailed(e) => write!(f, "decoding failed: {}", e),
+            Error::Crc(expected, got) => write!(
+                f,
+                "CRC mismatch, expected {:08x} but got {:08x}",
+                expected, got
+            ),
+            Error::InvalidSymlink => write!(f, "symbolic link target can't be decoded"),
+            Error::NotASymlink => write!(f, "entry is not a symbolic link"),
+            Error::UnsafeSymlinkTarget(target) => {
+                write!(f, "symbolic link target {:?} is unsafe", target)
+            }
+            Error::LimitExceeded(limit) => write!(f, "{} limit exceeded", limit),
+            #[cfg(feature = "std")]
+            Error::Io(kind) => write!(f, "I/O error: {:?}", kind),
+        };
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        return match self {
+            Error::Parser(e) => Some(e),
+            Error::CodecFailed(e) => Some(e),
+            _ => None,
+        };
     }
 }
@@ -0,0 +1,555 @@
+//! This module implements extracting entire archives to a directory on disk.
+
+use super::decode::decode_folder;
+use super::err::Error;
+use super::path::{sanitize_path, UnsafePathReason};
+use super::{EntryKind, FileHandle, FolderCache, InvalidNamePolicy, ReadOptions, Warning};
+use crate::parser::{parse_with_options, sevenz_crc};
+
+use alloc::string::String;
+use alloc::vec::Vec;
+use std::fs;
+use std::io;
+use std::path::{Component, Path, PathBuf};
+
+/// Why an entry was not extracted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SkipReason {
+    /// The entry's name would place it outside of the destination directory, or is otherwise unusable.
+    UnsafePath(UnsafePathReason),
+    /// The entry marks a file or directory as deleted, so there is nothing to extract.
+    AntiItem,
+    /// The entry is a symbolic link whose target is absolute or leads outside of the destination directory,
+    /// or which would be created through another link.
+    UnsafeSymlinkTarget,
+    /// A directory already exists where the entry would be created.
+    DirectoryExists,
+}
+
+/// Which targets `create_symlink()` accepts.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SymlinkPolicy {
+    /// Refuse targets which are absolute or lead outside of the root directory.
+    #[default]
+    WithinRoot,
+    /// Accept any target.
+    ///
+    /// Only use this for trusted archives, as writing through such links can modify arbitrary files.
+    Any,
+}
+
+/// An entry which was not extracted.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SkippedEntry {
+    /// The entry's name, with invalid UTF-16 escaped.
+    pub name: String,
+    pub reason: SkipReason,
+}
+
+/// An entry which could only be extracted partially or not at all, in recovery mode.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DamagedEntry {
+    /// The entry's name, with invalid UTF-16 escaped.
+    pub name: String,
+    /// Either `Error::Crc` if the entry was written but it's contents are wrong,
+    /// or why the contents couldn't be decoded.
+    pub error: Error,
+}
+
+/// Summary of what an extraction did.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ExtractionReport {
+    /// Paths of all files and directories written.
+    pub extracted: Vec<PathBuf>,
+    pub skipped: Vec<SkippedEntry>,
+    pub damaged: Vec<DamagedEntry>,
+    /// Damage to the archive itself which was worked around, see `ArchiveInfo::warnings`.
+    pub warnings: Vec<Warning>,
+}
+
+/// Join the sanitized components of the entry's name to the destination.
+fn entry_path(dest: &Path, name: &str) -> Result<PathBuf, UnsafePathReason> {
+    let mut path = dest.to_path_buf();
+    for component in sanitize_path(name)? {
+        // Guard against platform-specific interpretations, such as `C:` prefixes on Windows
+        let mut components = Path::new(component).components();
+        match (components.next(), components.next()) {
+            (Some(Component::Normal(c)), None) => path.push(c),
+            _ => return Err(UnsafePathReason::InvalidComponent),
+        }
+    }
+    return Ok(path);
+}
+
+/// How many links are followed when resolving a target, like the limit of most platforms.
+const MAX_SYMLINK_FOLLOWS: usize = 40;
+
+/// Whether the `target` of a symlink at `path` stays inside of `root`.
+///
+/// Components of the target which are links that already exist below `root` are followed,
+/// so `e -> d/..` is refused after `d -> .` was created.
+/// Components which don't exist yet are judged by their name only.
+/// Empty targets are refused, as they don't lead anywhere.
+fn target_within_root(root: &Path, path: &Path, target: &str) -> bool {
+    let parent = match path.parent().and_then(|p| p.strip_prefix(root).ok()) {
+        Some(p) => p,
+        None => return false,
+    };
+    if target.is_empty() {
+        return false;
+    }
+    // Components of the resolved path below root
+    let mut resolved: Vec<String> = Vec::new();
+    for component in parent.components() {
+        match component {
+            Component::Normal(c) => resolved.push(c.to_string_lossy().into_owned()),
+            Component::CurDir => (),
+            _ => return false,
+        }
+    }
+    // Components of the target still to be resolved, in reverse order
+    let mut pending: Vec<String> = Vec::new();
+    let mut follows = 0;
+    let mut target = String::from(target);
+    loop {
+        if target.starts_with('/') || target.starts_with('\\') {
+            return false;
+        }
+        pending.extend(target.split(['/', '\\']).rev().map(String::from));
+        let next = loop {
+            let component = match pending.pop() {
+                Some(c) => c,
+                None => return true,
+            };
+            match component.as_str() {
+                "" | "." => (),
+                ".." => {
+                    if resolved.pop().is_none() {
+                        return false;
+                    }
+                }
+                // Drive letters and alternate data streams
+                c if c.contains(':') => return false,
+                _ => {
+                    resolved.push(component);
+                    let dir = resolved.iter().fold(root.to_path_buf(), |p, c| p.join(c));
+                    if let Ok(link) = fs::read_link(&dir) {
+                        resolved.pop();
+                        break link;
+                    }
+                }
+            }
+        };
+        follows += 1;
+        if follows > MAX_SYMLINK_FOLLOWS {
+            return false;
+        }
+        target = next.to_string_lossy().into_owned();
+    }
+}
+
+/// Whether a directory between `root` and `path` is a symbolic link, which creating `path` would follow.
+///
+/// Directories which don't exist yet don't count, as they would be created as plain directories.
+fn has_symlink_ancestor(root: &Path, path: &Path) -> bool {
+    let mut dir = path.parent();
+    while let Some(d) = dir {
+        if d == root {
+            return false;
+        }
+        if fs::symlink_metadata(d).is_ok_and(|m| m.file_type().is_symlink()) {
+            return true;
+        }
+        dir = d.parent();
+    }
+    // Not inside of root at all
+    return true;
+}
+
+/// Create the symbolic link entry at `path`, replacing an existing file or link.
+///
+/// With `SymlinkPolicy::WithinRoot`, targets which are empty, absolute or would lead outside of `root`
+/// are refused with `Error::UnsafeSymlinkTarget`, in which case `path` must be inside of `root`.
+/// Links already created below `root` are followed when resolving the target.
+/// So are links whose parent directories below `root` include a link,
+/// such as `up/x -> ..` after `up -> .`, which would really be created at `x` and lead outside.
+/// Targets of links created on Windows have their separators converted on other platforms.
+/// An existing directory at `path` is not replaced, and fails with `Error::Io(ErrorKind::AlreadyExists)`.
+///
+/// Note that the check only considers links, so links must be created after
+/// all other entries to prevent writing through them.
+/// Links created later can also change where this one leads, see `extract_to_dir()`.
+pub fn create_symlink<'a>(
+    file: &FileHandle<'a>,
+    path: &Path,
+    root: &Path,
+    policy: SymlinkPolicy,
+) -> Result<(), Error> {
+    let target = match file.symlink_target()? {
+        Some(t) => t,
+        None => return Err(Error::NotASymlink),
+    };
+    if policy == SymlinkPolicy::WithinRoot
+        && (!target_within_root(root, path, &target) || has_symlink_ancestor(root, path))
+    {
+        return Err(Error::UnsafeSymlinkTarget(target));
+    }
+    #[cfg(not(windows))]
+    let target = match file.attributes() {
+        Some(a) if !a.has_unix_extension() => target.replace('\\', "/"),
+        _ => target,
+    };
+
+    if let Ok(m) = fs::symlink_metadata(path) {
+        if m.is_dir() {
+            return Err(Error::Io(io::ErrorKind::AlreadyExists));
+        }
+        fs::remove_file(path)?;
+    }
+    symlink(&target, path)?;
+    return Ok(());
+}
+
+#[cfg(unix)]
+fn symlink(target: &str, path: &Path) -> io::Result<()> {
+    return std::os::unix::fs::symlink(target, path);
+}
+
+#[cfg(windows)]
+fn symlink(target: &str, path: &Path) -> io::Result<()> {
+    // Windows distinguishes between links to files and directories
+    let target_is_dir = path
+        .parent()
+        .map(|p| p.join(target).is_dir())
+        .unwrap_or(false);
+    if target_is_dir {
+        return std::os::windows::fs::symlink_dir(target, path);
+    }
+    return std::os::windows::fs::symlink_file(target, path);
+}
+
+#[cfg(not(any(unix, windows)))]
+fn symlink(_target: &str, _path: &Path) -> io::Result<()> {
+    return Err(io::ErrorKind::Unsupported.into());
+}
+
+/// Write the entry to `path` and restore it's metadata using `restore_metadata()`.
+///
+/// Directory entries are created as directories, and anti-items are ignored.
+/// Symbolic links are written as regular files containing their target, use `create_symlink()` for them instead.
+/// The parent directory must already exist.
+///
+/// Note that the path is used as-is, so it must not be derived from the entry's name without sanitizing it first.
+pub fn extract_entry_to_path<'a>(file: &FileHandle<'a>, path: &Path) -> Result<(), Error> {
+    match file.kind() {
+        EntryKind::Directory => fs::create_dir_all(path)?,
+        EntryKind::AntiFile | EntryKind::AntiDirectory => return Ok(()),
+        EntryKind::File | EntryKind::EmptyFile => {
+            let mut out = fs::File::create(path)?;
+            file.extract_to_writer(&mut out)?;
+        }
+    }
+    return restore_metadata(file, path);
+}
+
+/// Like `extract_entry_to_path()` for regular files, but taking their folder from `cache`.
+///
+/// Returns the contents written.
+fn extract_file_cached<'a>(
+    file: &FileHandle<'a>,
+    path: &Path,
+    cache: &mut FolderCache<'a>,
+) -> Result<Vec<u8>, Error> {
+    let contents = file.extract_contents_cached(cache)?;
+    fs::write(path, &contents)?;
+    restore_metadata(file, path)?;
+    return Ok(contents);
+}
+
+/// Like `extract_file_cached()`,
+/// but failing with `Error::Crc` after writing the file if it's contents don't match it's CRC.
+fn extract_file_checked<'a>(
+    file: &FileHandle<'a>,
+    path: &Path,
+    cache: &mut FolderCache<'a>,
+) -> Result<(), Error> {
+    let contents = extract_file_cached(file, path, cache)?;
+    if let Some(expected) = file.crc() {
+        let got = sevenz_crc(&contents);
+        if got != expected {
+            return Err(Error::Crc(expected, got));
+        }
+    }
+    return Ok(());
+}
+
+/// Whether the error means the entry is damaged, rather than the extraction as a whole failing.
+fn is_damage(e: &Error) -> bool {
+    return matches!(
+        e,
+        Error::CodecFailed(_) | Error::Crc(..) | Error::InvalidSymlink
+    );
+}
+
+/// Apply the entry's timestamps and permissions to the file or directory at `path`.
+///
+/// The modification and access times are restored, as well as the creation time on Windows.
+/// On Unix, the permission bits from p7zip's Unix extension are restored if present,
+/// except for setuid, setgid and the sticky bit.
+/// Otherwise, only the read-only attribute is applied.
+/// Metadata which the platform doesn't support is silently ignored.
+///
+/// For directories, this should be called after their contents were written,
+/// as that would update the modification time and might be prevented by the permissions.
+/// This must not be called for symbolic links, as it would modify their target.
+pub fn restore_metadata<'a>(file: &FileHandle<'a>, path: &Path) -> Result<(), Error> {
+    set_times(file, path)?;
+    set_permissions(file, path)?;
+    return Ok(());
+}
+
+fn set_times(file: &FileHandle, path: &Path) -> io::Result<()> {
+    let mut times = fs::FileTimes::new();
+    let mut any = false;
+    if let Some(t) = file.modified().and_then(|t| t.to_system_time()) {
+        times = times.set_modified(t);
+        any = true;
+    }
+    if let Some(t) = file.accessed().and_then(|t| t.to_system_time()) {
+        times = times.set_accessed(t);
+        any = true;
+    }
+    #[cfg(windows)]
+    if let Some(t) = file.created().and_then(|t| t.to_system_time()) {
+        use std::os::windows::fs::FileTimesExt;
+        times = times.set_created(t);
+        any = true;
+    }
+    if !any {
+        return Ok(());
+    }
+    return match open_for_times(path)? {
+        Some(f) => f.set_times(times),
+        None => Ok(()),
+    };
+}
+
+/// Open the file or directory so that it's timestamps can be changed.
+#[cfg(unix)]
+fn open_for_times(path: &Path) -> io::Result<Option<fs::File>> {
+    return fs::File::open(path).map(Some);
+}
+
+/// Open the file or directory so that it's timestamps can be changed.
+#[cfg(windows)]
+fn open_for_times(path: &Path) -> io::Result<Option<fs::File>> {
+    use std::os::windows::fs::OpenOptionsExt;
+
+    const FILE_WRITE_ATTRIBUTES: u32 = 0x0100;
+    // Required to open directories
+    const FILE_FLAG_BACKUP_SEMANTICS: u32 = 0x0200_0000;
+    return fs::OpenOptions::new()
+        .access_mode(FILE_WRITE_ATTRIBUTES)
+        .custom_flags(FILE_FLAG_BACKUP_SEMANTICS)
+        .open(path)
+        .map(Some);
+}
+
+/// Open the file or directory so that it's timestamps can be changed.
+#[cfg(not(any(unix, windows)))]
+fn open_for_times(_path: &Path) -> io::Result<Option<fs::File>> {
+    return Ok(None);
+}
+
+#[cfg(unix)]
+fn set_permissions(file: &FileHandle, path: &Path) -> io::Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+
+    let attrs = match file.attributes() {
+        Some(a) => a,
+        None => return Ok(()),
+    };
+    let perms = match attrs.unix_mode() {
+        Some(mode) => fs::Permissions::from_mode(mode & 0o777),
+        None if attrs.is_read_only() => {
+            let mut perms = fs::metadata(path)?.permissions();
+            perms.set_readonly(true);
+            perms
+        }
+        None => return Ok(()),
+    };
+    return fs::set_permissions(path, perms);
+}
+
+#[cfg(windows)]
+fn set_permissions(file: &FileHandle, path: &Path) -> io::Result<()> {
+    if !file.attributes().is_some_and(|a| a.is_read_only()) {
+        return Ok(());
+    }
+    let mut perms = fs::metadata(path)?.permissions();
+    perms.set_readonly(true);
+    return fs::set_permissions(path, perms);
+}
+
+#[cfg(not(any(unix, windows)))]
+fn set_permissions(_file: &FileHandle, _path: &Path) -> io::Result<()> {
+    return Ok(());
+}
+
+/// Extract all entries of the archive into the `dest` directory, creating it if needed.
+///
+/// Entry names are sanitized so that nothing can be written outside of `dest`.
+/// Entries with unsafe names are skipped rather than failing the extraction, and listed in the returned report.
+/// Names which aren't valid UTF-16 are escaped according to `InvalidNamePolicy::Escape`.
+/// Existing files are overwritten.
+/// Fails with `Error::Crc` once a file was written whose contents don't match it's CRC.
+/// Timestamps and permissions are restored as described for `restore_metadata()`.
+/// Symbolic links are created last, and skipped if their target leads outside of `dest`.
+/// Once all links were created, those which now lead outside through links created after them are removed again.
+pub fn extract_to_dir(archive_data: &[u8], dest: &Path) -> Result<ExtractionReport, Error> {
+    return extract_to_dir_with_options(archive_data, dest, &ReadOptions::default());
+}
+
+/// Like `extract_to_dir()`, but with the given codecs, limits and recovery mode.
+///
+/// In recovery mode, entries which fail to decode or don't match their CRC are listed as damaged
+/// instead of failing the extraction.
+/// Files which decoded but don't match their CRC are still written.
+pub fn extract_to_dir_with_options(
+    archive_data: &[u8],
+    dest: &Path,
+    options: &ReadOptions,
+) -> Result<ExtractionReport, Error> {
+    let ar = parse_with_options(archive_data, options, decode_folder)?;
+    let files = (0..ar.files.len()).map(|i| FileHandle::new(&ar, archive_data, i));
+    let mut report = ExtractionReport {
+        warnings: ar.info.warnings.clone(),
+        ..Default::default()
+    };
+    // Files are extracted in the order they're stored, so each folder is decoded once
+    let mut cache = FolderCache::new(0);
+    // Directory metadata is restored once everything else was written
+    let mut dirs = Vec::new();
+    let mut links = Vec::new();
+    fs::create_dir_all(dest)?;
+
+    for file in files {
+        let name = file.name_with_policy(InvalidNamePolicy::Escape)?;
+        let kind = file.kind();
+        if kind == EntryKind::AntiFile || kind == EntryKind::AntiDirectory {
+            report.skipped.push(SkippedEntry {
+                name,
+                reason: SkipReason::AntiItem,
+            });
+            continue;
+        }
+        let path = match entry_path(dest, &name) {
+            Ok(p) => p,
+            Err(reason) => {
+                report.skipped.push(SkippedEntry {
+                    name,
+                    reason: SkipReason::UnsafePath(reason),
+                });
+                continue;
+            }
+        };
+
+        if kind == EntryKind::Directory {
+            fs::create_dir_all(&path)?;
+            dirs.push((file, path.clone()));
+        } else if file.is_symlink() {
+            links.push((file, name, path));
+            continue;
+        } else {
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            if !options.recover {
+                extract_file_checked(&file, &path, &mut cache)?;
+            } else if let Err(error) = extract_file_checked(&file, &path, &mut cache) {
+                if !is_damage(&error) {
+                    return Err(error);
+                }
+                let written = matches!(error, Error::Crc(..));
+                report.damaged.push(DamagedEntry { name, error });
+                if !written {
+                    continue;
+                }
+            }
+        }
+        report.extracted.push(path);
+    }
+
+    let mut created = Vec::new();
+    for (file, name, path) in links {
+        // Creating the parent directories would follow links created before
+        if has_symlink_ancestor(dest, &path) {
+            report.skipped.push(SkippedEntry {
+                name,
+                reason: SkipReason::UnsafeSymlinkTarget,
+            });
+            continue;
+        }
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        match create_symlink(&file, &path, dest, SymlinkPolicy::WithinRoot) {
+            Ok(()) => {
+                created.push((name, path.clone()));
+                report.extracted.push(path);
+            }
+            Err(Error::UnsafeSymlinkTarget(_)) => report.skipped.push(SkippedEntry {
+                name,
+                reason: SkipReason::UnsafeSymlinkTarget,
+            }),
+            Err(Error::Io(io::ErrorKind::AlreadyExists)) => report.skipped.push(SkippedEntry {
+                name,
+                reason: SkipReason::DirectoryExists,
+            }),
+            Err(error) if options.recover && is_damage(&error) => {
+                report.damaged.push(DamagedEntry { name, error })
+            }
+            Err(e) => return Err(e),
+        }
+    }
+
+    // Removing a link can change where others lead, so check until none escape
+    while let Some(i) = created.iter().position(|(_, path)| {
+        let target = fs::read_link(path).map(|t| t.to_string_lossy().into_owned());
+        !target.is_ok_and(|t| target_within_root(dest, path, &t))
+    }) {
+        let (name, path) = created.remove(i);
+        fs::remove_file(&path)?;
+        report.extracted.retain(|p| *p != path);
+        report.skipped.push(SkippedEntry {
+            name,
+            reason: SkipReason::UnsafeSymlinkTarget,
+        });
+    }
+
+    // Deepest directories first, so restoring a parent's metadata isn't undone by it's children
+    dirs.sort_by_key(|(_, path)| core::cmp::Reverse(path.components().count()));
+    for (file, path) in dirs {
+        restore_metadata(&file, &path)?;
+    }
+    return Ok(report);
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn target_within_root() {
+        let root = Path::new("/nonexistent/sevenz-test-root");
+        let within =
+            |path: &str, target: &str| super::target_within_root(root, &root.join(path), target);
+        assert!(within("l", "a/b/.."));
+        assert!(within("a/l", "../b"));
+        assert!(!within("l", ""));
+        assert!(!within("l", ".."));
+        assert!(!within("a/l", "../../b"));
+        assert!(!within("l", "/etc"));
+        assert!(!within("l", "C:x"));
+    }
+}
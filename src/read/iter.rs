@@ -1,28 +1,73 @@
-use super::FileHandle;
+use super::err::Error;
+use super::{FileHandle, FolderCache};
 use crate::parser::HighLevelArchive;
 
+use alloc::vec::Vec;
 use core::iter::Iterator;
 
 /// Iterates over each file in the archive.
 /// Actual extraction occurs only once the file's contents are requested.
+/// To extract all of them, use `ContentsIterator` instead, which decodes each folder once.
 ///
 /// Underlying `Archive` must live at least as long.
 #[derive(Debug, Clone)]
 pub struct ArchiveIterator<'a> {
     ar: &'a HighLevelArchive,
+    /// The entire archive's unprocessed data.
+    archive_raw: &'a [u8],
+    /// Index of the next file to be returned.
+    index: usize,
 }
 
 impl<'a> ArchiveIterator<'a> {
-    /// Create a new iterator over the given `Archive`.
-    pub fn new(ar: &'a HighLevelArchive) -> ArchiveIterator<'a> {
-        ArchiveIterator { ar }
+    /// Create a new iterator over the given `Archive`, which was parsed from `archive_raw`.
+    pub fn new(ar: &'a HighLevelArchive, archive_raw: &'a [u8]) -> ArchiveIterator<'a> {
+        ArchiveIterator {
+            ar,
+            archive_raw,
+            index: 0,
+        }
     }
 }
 
 impl<'a> Iterator for ArchiveIterator<'a> {
     type Item = FileHandle<'a>;
     fn next(&mut self) -> Option<FileHandle<'a>> {
-        // FIXME: Implement
-        None
+        if self.index >= self.ar.files.len() {
+            return None;
+        }
+        let handle = FileHandle::new(self.ar, self.archive_raw, self.index);
+        self.index += 1;
+        return Some(handle);
+    }
+}
+
+/// Iterates over each file in the archive together with it's contents,
+/// decoding each folder once as long as the files sharing it are stored next to each other,
+/// as they are in archives created by 7-Zip.
+///
+/// Directories, empty files and anti-items have empty contents.
+#[derive(Debug, Clone)]
+pub struct ContentsIterator<'a> {
+    files: ArchiveIterator<'a>,
+    cache: FolderCache<'a>,
+}
+
+impl<'a> ContentsIterator<'a> {
+    /// Iterate over the files returned by `files`, caching their folders in a `FolderCache` of `cache_size` bytes.
+    pub fn new(files: ArchiveIterator<'a>, cache_size: usize) -> ContentsIterator<'a> {
+        ContentsIterator {
+            files,
+            cache: FolderCache::new(cache_size),
+        }
+    }
+}
+
+impl<'a> Iterator for ContentsIterator<'a> {
+    type Item = (FileHandle<'a>, Result<Vec<u8>, Error>);
+    fn next(&mut self) -> Option<Self::Item> {
+        let file = self.files.next()?;
+        let contents = file.extract_contents_cached(&mut self.cache);
+        return Some((file, contents));
     }
 }
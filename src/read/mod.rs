@@ -1,19 +1,53 @@
 //! This module implements an interface for reading 7zip archives.
 
-mod decode;
+mod archive;
+mod attributes;
+mod cache;
+pub(crate) mod decode;
 mod err;
+#[cfg(feature = "std")]
+mod extract;
 mod iter;
+mod name;
+mod path;
 mod simplistic;
+mod symlink;
+mod time;
+mod verify;
 
 use crate::parser::File;
+use crate::parser::FolderInfo;
 use crate::parser::HighLevelArchive;
 
+pub use crate::codec::{
+    AesProperties, Codec, CodecError, CodecFactory, CodecRegistry, DeltaProperties, Encoder,
+    Lzma2Properties, LzmaProperties, Method, PpmdProperties,
+};
+pub use crate::parser::ArchiveInfo;
+pub use crate::parser::EntryKind;
+pub use crate::parser::{
+    ErrorContext, SevenZConversionError, SevenZParserError, SevenZParserErrorKind, Warning,
+};
+
+pub use crate::options::*;
+pub use archive::*;
+pub use attributes::*;
+pub use cache::*;
 pub use err::*;
+#[cfg(feature = "std")]
+pub use extract::*;
 pub use iter::*;
+pub use name::InvalidNamePolicy;
+pub use path::*;
 pub use simplistic::*;
+pub use time::FileTime;
+pub use verify::*;
 
-use alloc::string::String;
+use alloc::string::{String, ToString};
 use alloc::vec::Vec;
+use core::convert::TryFrom;
+use core::ops::Range;
+use widestring::U16String;
 
 /// The handle for a file contained within an archive.
 ///
@@ -22,18 +56,32 @@ use alloc::vec::Vec;
 /// it can't live independently of it's underlying archive.
 pub struct FileHandle<'a> {
     pub(crate) underlying: File,
-    // TODO: Expose ctime, mtime, atime, compressed size, uncompressed size and other useful attributes.
+    /// The folder containing the file's data, unless it's got none.
+    pub(crate) folder: Option<FolderInfo>,
     /// The entire archive's unprocessed data.
     pub(crate) archive_raw: &'a [u8],
+    /// The codecs and limits used to decode the file's contents.
+    pub(crate) options: ReadOptions,
 }
 
 impl<'a> FileHandle<'a> {
     /// Create a new handle based on the given archive and file index.
-    fn new(ar: &'a HighLevelArchive, archive_raw: &'a [u8], index: usize) -> FileHandle<'a> {
+    fn new(ar: &HighLevelArchive, archive_raw: &'a [u8], index: usize) -> FileHandle<'a> {
         let underlying = ar.files[index].clone();
+        let folder = underlying
+            .stream
+            .as_ref()
+            .map(|s| ar.folders[s.folder_index].clone());
         return FileHandle {
             underlying,
+            folder,
             archive_raw,
+            options: ReadOptions {
+                registry: ar.registry.clone(),
+                limits: ar.limits,
+                // Damage to the file's contents is reported when extracting it either way
+                recover: false,
+            },
         };
     }
 
@@ -46,16 +94,239 @@ impl<'a> FileHandle<'a> {
     /// It's recommended that you only use this method if you have checked that the file will fit using `FileHandle::size()`.
     ///
     /// Otherwise, you program will likely run out of memory.
-    pub fn extract_contents_vec(&self) -> Result<Vec<u8>, err::Error<'a>> {
-        let contents_packed = self.underlying.subslice_raw(self.archive_raw);
-        let contents_unpacked = decode::decode_file(contents_packed, &self.underlying.stream)?;
-        return Ok(contents_unpacked);
+    /// Directories, empty files and anti-items have no contents, so an empty vector is returned for them.
+    ///
+    /// The whole folder containing the file is decoded each time, so calling this for every file
+    /// of an `ArchiveIterator` takes quadratic time for solid archives.
+    /// Use `Archive::contents()` or `FileHandle::extract_contents_cached()` instead.
+    pub fn extract_contents_vec(&self) -> Result<Vec<u8>, err::Error> {
+        let (folder_unpacked, range) = self.decode_folder()?;
+        return Ok(Vec::from(&folder_unpacked[range]));
+    }
+
+    /// Like `FileHandle::extract_contents_vec()`, but taking the folder containing the file from `cache`,
+    /// or adding it once decoded.
+    ///
+    /// This avoids decoding a folder shared by many files over and over, see `FolderCache`.
+    pub fn extract_contents_cached(
+        &self,
+        cache: &mut FolderCache<'a>,
+    ) -> Result<Vec<u8>, err::Error> {
+        let (folder_unpacked, range) = self.decode_folder_with(|index, folder| {
+            return cache.get(self.archive_raw, index, folder, &self.options);
+        })?;
+        return Ok(Vec::from(&folder_unpacked[range]));
+    }
+
+    /// Extract the file's contents into the given writer, returning the number of bytes written.
+    ///
+    /// Note that the folder containing the file is currently still decoded into RAM in it's entirety.
+    #[cfg(feature = "std")]
+    pub fn extract_to_writer<W: std::io::Write>(&self, w: &mut W) -> Result<u64, err::Error> {
+        let (folder_unpacked, range) = self.decode_folder()?;
+        let contents = &folder_unpacked[range];
+        w.write_all(contents)?;
+        return Ok(contents.len() as u64);
+    }
+
+    /// Decode the folder containing this file.
+    ///
+    /// Returns the decoded folder and the range within it containing this file's data,
+    /// which is empty if the file has no contents.
+    fn decode_folder(&self) -> Result<(Vec<u8>, Range<usize>), err::Error> {
+        return self.decode_folder_with(|_, folder| {
+            return Ok(decode::decode_folder(
+                self.archive_raw,
+                folder,
+                &self.options,
+            )?);
+        });
+    }
+
+    /// Like `FileHandle::decode_folder()`, but decoding the folder using `decode`,
+    /// which is passed it's index and description.
+    fn decode_folder_with<T: AsRef<Vec<u8>> + Default>(
+        &self,
+        decode: impl FnOnce(usize, &FolderInfo) -> Result<T, err::Error>,
+    ) -> Result<(T, Range<usize>), err::Error> {
+        let (stream, folder) = match (&self.underlying.stream, &self.folder) {
+            (Some(stream), Some(folder)) => (stream, folder),
+            _ => return Ok((T::default(), 0..0)),
+        };
+        Limits::check(
+            stream.size,
+            self.options.limits.max_entry_size,
+            Limit::EntrySize,
+        )
+        .map_err(err::Error::LimitExceeded)?;
+        let folder_unpacked = decode(stream.folder_index, folder)?;
+        // The stream must lie within the decoded folder, whatever the archive claims
+        let range = usize::try_from(stream.offset)
+            .ok()
+            .zip(usize::try_from(stream.size).ok())
+            .and_then(|(offset, size)| Some(offset..offset.checked_add(size)?))
+            .filter(|r| r.end <= folder_unpacked.as_ref().len())
+            .ok_or(CodecError::InvalidData)?;
+        return Ok((folder_unpacked, range));
+    }
+
+    /// Get the size of this file's contents once extracted.
+    pub fn size(&self) -> u64 {
+        return self.underlying.stream.as_ref().map(|s| s.size).unwrap_or(0);
+    }
+
+    /// Get the size of this file's contents in the archive.
+    ///
+    /// If the file shares it's folder with others (as in solid archives), their packed data can't be told apart,
+    /// so the folder's packed size is split in proportion to the unpacked sizes instead.
+    pub fn packed_size(&self) -> u64 {
+        let (stream, folder) = match (&self.underlying.stream, &self.folder) {
+            (Some(stream), Some(folder)) => (stream, folder),
+            _ => return 0,
+        };
+        let folder_packed_size: u64 = folder.packed_streams.iter().map(|s| s.size).sum();
+        if folder.num_unpack_streams == 1 || folder.unpack_size == 0 {
+            return folder_packed_size;
+        }
+        let share = folder_packed_size as u128 * stream.size as u128 / folder.unpack_size as u128;
+        return share as u64;
+    }
+
+    /// Get the CRC32 of this file's contents, if the archive stores it.
+    pub fn crc(&self) -> Option<u32> {
+        return self.underlying.stream.as_ref().and_then(|s| s.crc);
+    }
+
+    /// Get the index of the folder containing this file's contents, unless it's got none.
+    pub fn folder_index(&self) -> Option<usize> {
+        return self.underlying.stream.as_ref().map(|s| s.folder_index);
+    }
+
+    /// Get the methods used to store this file's contents, like `7z l -slt` would show them.
+    ///
+    /// The first method is the one applied last when packing, for example `LZMA2:24 BCJ`.
+    pub fn method(&self) -> Option<String> {
+        let folder = self.folder.as_ref()?;
+        let names: Vec<String> = folder
+            .coders
+            .iter()
+            .rev()
+            .map(|c| c.method().to_string())
+            .collect();
+        return Some(names.join(" "));
+    }
+
+    /// Get the methods used to store this file's contents, in the same order as `FileHandle::method()`.
+    pub fn methods(&self) -> Vec<Method> {
+        return match &self.folder {
+            Some(folder) => folder.coders.iter().rev().map(|c| c.method()).collect(),
+            None => Vec::new(),
+        };
     }
 
     /// Get the name of this file.
+    ///
+    /// Invalid UTF-16 in the name is replaced by U+FFFD REPLACEMENT CHARACTER,
+    /// see `FileHandle::name_with_policy()` for other options.
     pub fn name(&self) -> String {
+        // Can't fail with this policy
+        return name::to_string(&self.underlying.name, InvalidNamePolicy::Lossy).unwrap();
+    }
+
+    /// Get the name of this file, handling invalid UTF-16 according to the given policy.
+    pub fn name_with_policy(&self, policy: InvalidNamePolicy) -> Result<String, err::Error> {
+        return match name::to_string(&self.underlying.name, policy) {
+            Some(n) => Ok(n),
+            None => Err(err::Error::InvalidFileName(self.underlying.name.clone())),
+        };
+    }
+
+    /// Get the name of this file exactly as stored in the archive.
+    pub fn name_wide(&self) -> U16String {
         return self.underlying.name.clone();
     }
 
-    // TODO: Implement method to get contents into a `std::io::Write` or preferably a `no_std`-friendly equivalent.
+    /// Get the name of this file as an `OsString`, without losing any information.
+    #[cfg(all(feature = "std", any(unix, windows)))]
+    pub fn name_os(&self) -> std::ffi::OsString {
+        return name::to_os_string(&self.underlying.name);
+    }
+
+    /// Get the comment of this file, if it's got one.
+    ///
+    /// Invalid UTF-16 in the comment is replaced by U+FFFD REPLACEMENT CHARACTER.
+    pub fn comment(&self) -> Option<String> {
+        return self
+            .underlying
+            .comment
+            .as_ref()
+            .map(|c| name::to_string(c, InvalidNamePolicy::Lossy).unwrap());
+    }
+
+    /// Get what kind of entry this is.
+    pub fn kind(&self) -> EntryKind {
+        return self.underlying.kind;
+    }
+
+    /// Get the attributes of this file, if the archive stores them.
+    pub fn attributes(&self) -> Option<Attributes> {
+        return self.underlying.attributes.map(Attributes::new);
+    }
+
+    /// Whether this entry is a symbolic link.
+    ///
+    /// Both p7zip's Unix mode and Windows reparse points are recognized.
+    pub fn is_symlink(&self) -> bool {
+        if self.kind() != EntryKind::File {
+            return false;
+        }
+        return match self.attributes() {
+            Some(a) if a.has_unix_extension() => a.unix_file_type() == Some(UnixFileType::Symlink),
+            Some(a) => a.is_reparse_point(),
+            None => false,
+        };
+    }
+
+    /// Get the target of this symbolic link, or `None` if it isn't one.
+    ///
+    /// The target is returned as stored, so links created on Windows use `\` as separator.
+    pub fn symlink_target(&self) -> Result<Option<String>, err::Error> {
+        if !self.is_symlink() {
+            return Ok(None);
+        }
+        let contents = self.extract_contents_vec()?;
+        // Checked by `is_symlink()`
+        let attrs = self.attributes().unwrap();
+        let target = if attrs.has_unix_extension() {
+            String::from_utf8(contents).ok()
+        } else {
+            symlink::reparse_point_target(&contents)
+        };
+        return match target {
+            Some(t) => Ok(Some(t)),
+            None => Err(err::Error::InvalidSymlink),
+        };
+    }
+
+    /// Get the position of this file's data within the original file, if the archive stores it.
+    ///
+    /// This is only set for archives created from parts of files.
+    pub fn start_pos(&self) -> Option<u64> {
+        return self.underlying.start_pos;
+    }
+
+    /// Get the creation time of this file, if the archive stores it.
+    pub fn created(&self) -> Option<FileTime> {
+        return self.underlying.ctime.map(FileTime::new);
+    }
+
+    /// Get the last access time of this file, if the archive stores it.
+    pub fn accessed(&self) -> Option<FileTime> {
+        return self.underlying.atime.map(FileTime::new);
+    }
+
+    /// Get the last modification time of this file, if the archive stores it.
+    pub fn modified(&self) -> Option<FileTime> {
+        return self.underlying.mtime.map(FileTime::new);
+    }
 }
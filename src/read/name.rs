@@ -0,0 +1,105 @@
+//! This module implements converting file names stored in the archive into native strings.
+//!
+//! Names are stored as UTF-16, but archives created on Windows may contain names with
+//! unpaired surrogates, which can't be represented as a `String`.
+
+use alloc::string::String;
+use widestring::U16Str;
+
+/// What to do with file names which aren't valid UTF-16 when converting them to a `String`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InvalidNamePolicy {
+    /// Replace each unpaired surrogate with U+FFFD REPLACEMENT CHARACTER.
+    ///
+    /// Note that this may cause distinct names to collide.
+    Lossy,
+    /// Replace each unpaired surrogate with `%uXXXX`, where `XXXX` is it's value in hex.
+    Escape,
+    /// Refuse to convert the name.
+    Error,
+}
+
+/// Convert the name to a `String` according to the given policy.
+///
+/// Returns `None` if the name is invalid and the policy is `InvalidNamePolicy::Error`.
+pub(crate) fn to_string(name: &U16Str, policy: InvalidNamePolicy) -> Option<String> {
+    let mut res = String::with_capacity(name.len());
+    for c in char::decode_utf16(name.as_slice().iter().copied()) {
+        match c {
+            Ok(c) => res.push(c),
+            Err(e) => match policy {
+                InvalidNamePolicy::Lossy => res.push(char::REPLACEMENT_CHARACTER),
+                InvalidNamePolicy::Escape => {
+                    res.push_str(&alloc::format!("%u{:04X}", e.unpaired_surrogate()))
+                }
+                InvalidNamePolicy::Error => return None,
+            },
+        }
+    }
+    return Some(res);
+}
+
+/// Convert the name to an `OsString` without losing any information.
+///
+/// On Unix, unpaired surrogates are encoded as WTF-8, which means that the resulting name may not be valid UTF-8.
+#[cfg(all(feature = "std", unix))]
+pub(crate) fn to_os_string(name: &U16Str) -> std::ffi::OsString {
+    use alloc::vec::Vec;
+    use std::os::unix::ffi::OsStringExt;
+
+    let mut bytes = Vec::with_capacity(name.len());
+    for c in char::decode_utf16(name.as_slice().iter().copied()) {
+        match c {
+            Ok(c) => bytes.extend_from_slice(c.encode_utf8(&mut [0; 4]).as_bytes()),
+            Err(e) => {
+                let s = e.unpaired_surrogate();
+                bytes.push(0xE0 | (s >> 12) as u8);
+                bytes.push(0x80 | ((s >> 6) & 0x3F) as u8);
+                bytes.push(0x80 | (s & 0x3F) as u8);
+            }
+        }
+    }
+    return std::ffi::OsString::from_vec(bytes);
+}
+
+/// Convert the name to an `OsString` without losing any information.
+#[cfg(all(feature = "std", windows))]
+pub(crate) fn to_os_string(name: &U16Str) -> std::ffi::OsString {
+    use std::os::windows::ffi::OsStringExt;
+
+    return std::ffi::OsString::from_wide(name.as_slice());
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use widestring::U16String;
+
+    #[test]
+    fn invalid_name_policies() {
+        // "a", unpaired high surrogate, "b"
+        let name = U16String::from_vec(alloc::vec![0x61, 0xD800, 0x62]);
+        assert_eq!(
+            to_string(&name, InvalidNamePolicy::Lossy).unwrap(),
+            "a\u{FFFD}b"
+        );
+        assert_eq!(
+            to_string(&name, InvalidNamePolicy::Escape).unwrap(),
+            "a%uD800b"
+        );
+        assert_eq!(to_string(&name, InvalidNamePolicy::Error), None);
+
+        let name = U16String::from_str("välid");
+        assert_eq!(to_string(&name, InvalidNamePolicy::Error).unwrap(), "välid");
+    }
+
+    #[cfg(all(feature = "std", unix))]
+    #[test]
+    fn os_string_is_lossless() {
+        use std::os::unix::ffi::OsStrExt;
+
+        let name = U16String::from_vec(alloc::vec![0x61, 0xD800, 0x62]);
+        let os = to_os_string(&name);
+        assert_eq!(os.as_bytes(), &[0x61, 0xED, 0xA0, 0x80, 0x62]);
+    }
+}
@@ -0,0 +1,197 @@
+//! This module implements turning entry names into paths which are safe to extract to.
+//!
+//! Entry names are arbitrary strings, which may use `\` as a separator or try to
+//! escape the destination directory using absolute paths, drive letters or `..` (also known as "zip-slip").
+
+use alloc::vec;
+use alloc::vec::Vec;
+
+/// Why an entry name could not be turned into a safe relative path.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UnsafePathReason {
+    /// The name has no components left once `.` and empty components are removed.
+    Empty,
+    /// The name starts with a separator.
+    Absolute,
+    /// The name starts with a drive letter, like `C:`.
+    DriveLetter,
+    /// The name contains a `..` component.
+    ParentDirectory,
+    /// The name contains a NUL character.
+    Nul,
+    /// A component would not be treated as a plain file name by the platform,
+    /// for example because it contains a drive letter on Windows.
+    InvalidComponent,
+}
+
+/// Split the entry name into components which can be safely joined to a destination directory.
+///
+/// Both `/` and `\` are treated as separators, and `.` or empty components are dropped.
+pub fn sanitize_path(name: &str) -> Result<Vec<&str>, UnsafePathReason> {
+    if name.contains('\0') {
+        return Err(UnsafePathReason::Nul);
+    }
+    if name.starts_with('/') || name.starts_with('\\') {
+        return Err(UnsafePathReason::Absolute);
+    }
+    let mut chars = name.chars();
+    if let (Some(letter), Some(':')) = (chars.next(), chars.next()) {
+        if letter.is_ascii_alphabetic() {
+            return Err(UnsafePathReason::DriveLetter);
+        }
+    }
+
+    let mut components = Vec::new();
+    for component in name.split(['/', '\\']) {
+        match component {
+            "" | "." => (),
+            ".." => return Err(UnsafePathReason::ParentDirectory),
+            c => components.push(c),
+        }
+    }
+    if components.is_empty() {
+        return Err(UnsafePathReason::Empty);
+    }
+    return Ok(components);
+}
+
+/// A part of a glob pattern, see `glob_match()`.
+enum GlobToken {
+    /// `*`
+    Star,
+    /// `**`
+    DoubleStar,
+    /// `**/`, which may also match nothing at all.
+    Dirs,
+    /// `?`
+    Question,
+    /// `/` or `\`
+    Separator,
+    Char(char),
+}
+
+fn is_separator(c: char) -> bool {
+    return c == '/' || c == '\\';
+}
+
+/// Whether the entry `name` matches the glob `pattern`.
+///
+/// `?` matches any single character and `*` any number of them, except for separators.
+/// `**` matches any number of characters including separators, and `**/` also matches nothing,
+/// so that `**/*.txt` matches `a.txt` as well as `dir/a.txt`.
+/// Both `/` and `\` are treated as separators and match each other.
+pub fn glob_match(pattern: &str, name: &str) -> bool {
+    let mut tokens = Vec::new();
+    let mut chars = pattern.chars().peekable();
+    while let Some(c) = chars.next() {
+        let token = match c {
+            '*' if chars.peek() == Some(&'*') => {
+                chars.next();
+                if chars.peek().copied().is_some_and(is_separator) {
+                    chars.next();
+                    GlobToken::Dirs
+                } else {
+                    GlobToken::DoubleStar
+                }
+            }
+            '*' => GlobToken::Star,
+            '?' => GlobToken::Question,
+            c if is_separator(c) => GlobToken::Separator,
+            c => GlobToken::Char(c),
+        };
+        tokens.push(token);
+    }
+
+    // matches[i] is whether the tokens processed so far match name[i..],
+    // going from the last token to the first so that each step is linear
+    let name: Vec<char> = name.chars().collect();
+    let mut matches = vec![false; name.len() + 1];
+    matches[name.len()] = true;
+    for token in tokens.iter().rev() {
+        let mut next = vec![false; name.len() + 1];
+        // Whether some separator at or after i is followed by a match, for `**/`
+        let mut dirs = false;
+        for i in (0..=name.len()).rev() {
+            let c = name.get(i).copied();
+            next[i] = match token {
+                GlobToken::Star => {
+                    matches[i] || (c.is_some_and(|c| !is_separator(c)) && next[i + 1])
+                }
+                GlobToken::DoubleStar => matches[i] || (c.is_some() && next[i + 1]),
+                GlobToken::Dirs => {
+                    dirs |= c.is_some_and(is_separator) && matches[i + 1];
+                    matches[i] || dirs
+                }
+                GlobToken::Question => c.is_some_and(|c| !is_separator(c)) && matches[i + 1],
+                GlobToken::Separator => c.is_some_and(is_separator) && matches[i + 1],
+                GlobToken::Char(p) => c == Some(*p) && matches[i + 1],
+            };
+        }
+        matches = next;
+    }
+    return matches[0];
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use alloc::vec;
+
+    #[test]
+    fn sanitize_path() {
+        let test_cases: &[(&str, Result<Vec<&str>, UnsafePathReason>)] = &[
+            ("file.txt", Ok(vec!["file.txt"])),
+            ("dir/file.txt", Ok(vec!["dir", "file.txt"])),
+            ("dir\\sub\\file.txt", Ok(vec!["dir", "sub", "file.txt"])),
+            ("./dir//file.txt", Ok(vec!["dir", "file.txt"])),
+            ("dir/..file", Ok(vec!["dir", "..file"])),
+            ("", Err(UnsafePathReason::Empty)),
+            ("./.", Err(UnsafePathReason::Empty)),
+            ("/etc/passwd", Err(UnsafePathReason::Absolute)),
+            ("\\\\server\\share", Err(UnsafePathReason::Absolute)),
+            ("C:\\Windows", Err(UnsafePathReason::DriveLetter)),
+            ("c:file", Err(UnsafePathReason::DriveLetter)),
+            ("../evil", Err(UnsafePathReason::ParentDirectory)),
+            ("dir\\..\\..\\evil", Err(UnsafePathReason::ParentDirectory)),
+            ("evil\0.txt", Err(UnsafePathReason::Nul)),
+        ];
+
+        for (name, expected) in test_cases {
+            assert_eq!(super::sanitize_path(name), *expected, "{}", name);
+        }
+    }
+
+    #[test]
+    fn glob_match() {
+        let test_cases: &[(&str, &str, bool)] = &[
+            ("file.txt", "file.txt", true),
+            ("file.txt", "file.txt2", false),
+            ("*.txt", "file.txt", true),
+            ("*.txt", "dir/file.txt", false),
+            ("dir/*", "dir\\file.txt", true),
+            ("dir/*", "dir/sub/file.txt", false),
+            ("dir/**", "dir/sub/file.txt", true),
+            ("**.txt", "dir/sub/file.txt", true),
+            ("**/*.txt", "file.txt", true),
+            ("**/*.txt", "dir/sub/file.txt", true),
+            ("dir/**/file.txt", "dir/file.txt", true),
+            ("dir/**/file.txt", "dirfile.txt", false),
+            ("?.txt", "a.txt", true),
+            ("?.txt", "/.txt", false),
+            ("?.txt", "ab.txt", false),
+            ("*", "", true),
+            ("", "file.txt", false),
+            ("a*a*a*a*b", "aaaaaaaaaaaaaaaaaaaaaaaaaaaaaa", false),
+        ];
+
+        for (pattern, name, expected) in test_cases {
+            assert_eq!(
+                super::glob_match(pattern, name),
+                *expected,
+                "{} {}",
+                pattern,
+                name
+            );
+        }
+    }
+}
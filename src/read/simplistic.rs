@@ -3,30 +3,77 @@
 //! It trades off precise control for ease of use.
 
 use super::err::Error;
-use super::iter::ArchiveIterator;
+use super::Archive;
+use super::FileHandle;
+use super::ReadOptions;
 
-use crate::parser::parse;
+use super::decode::decode_folder;
+use crate::codec::CodecRegistry;
+use crate::parser::ArchiveInfo;
+use crate::parser::{parse, parse_with_options};
 
 use alloc::string::String;
+use alloc::sync::Arc;
 use alloc::vec::Vec;
 
+/// Retrieve handles for all files in the archive, in the order they're stored.
+pub fn list_files<'a>(archive_data: &'a [u8]) -> Result<Vec<FileHandle<'a>>, Error> {
+    return list_files_with_options(archive_data, &ReadOptions::default());
+}
+
+/// Like `list_files()`, but decoding the archive using the codecs in `registry`.
+pub fn list_files_with_registry<'a>(
+    archive_data: &'a [u8],
+    registry: Arc<CodecRegistry>,
+) -> Result<Vec<FileHandle<'a>>, Error> {
+    let options = ReadOptions {
+        registry,
+        ..Default::default()
+    };
+    return list_files_with_options(archive_data, &options);
+}
+
+/// Like `list_files()`, but with the given codecs and limits.
+pub fn list_files_with_options<'a>(
+    archive_data: &'a [u8],
+    options: &ReadOptions,
+) -> Result<Vec<FileHandle<'a>>, Error> {
+    let ar = parse_with_options(archive_data, options, decode_folder)?;
+    let files = (0..ar.files.len())
+        .map(|i| FileHandle::new(&ar, archive_data, i))
+        .collect();
+    return Ok(files);
+}
+
+/// Retrieve information about the archive as a whole.
+pub fn archive_info(archive_data: &[u8]) -> Result<ArchiveInfo, Error> {
+    let ar = parse(archive_data, decode_folder)?;
+    return Ok(ar.info);
+}
+
+/// Like `archive_info()`, but with the given codecs, limits and recovery mode.
+///
+/// In recovery mode, the damage which was worked around is listed in `ArchiveInfo::warnings`.
+pub fn archive_info_with_options(
+    archive_data: &[u8],
+    options: &ReadOptions,
+) -> Result<ArchiveInfo, Error> {
+    let ar = parse_with_options(archive_data, options, decode_folder)?;
+    return Ok(ar.info);
+}
+
+/// Retrieve the archive's comment, if it's got one.
+///
+/// Invalid UTF-16 in the comment is replaced by U+FFFD REPLACEMENT CHARACTER.
+pub fn archive_comment(archive_data: &[u8]) -> Result<Option<String>, Error> {
+    let ar = parse(archive_data, decode_folder)?;
+    return Ok(ar.comment.map(|c| c.to_string_lossy()));
+}
+
 /// Extract the file with the given `name` into a data buffer.
 ///
-/// This involves parsing the entire archive and
-/// iterating over all file descriptors for each file extracted, so it's not very efficient.
-pub fn extract_file<'a>(name: &str, archive_data: &'a [u8]) -> Result<Vec<u8>, Error<'a>> {
-    let ar = parse(archive_data)?;
-    let files = ArchiveIterator::new(&ar);
-    let file = match files.filter(|x| x.name() == name).next() {
-        Some(f) => f,
-        None => return Err(Error::NoSuchFileName(String::from(name))),
-    };
-    let contents = match file.extract_contents_vec() {
-        Ok(contents) => contents,
-        Err(e) => {
-            // FIXME: Bubble up the error instead (requires decoupling error's lifetime from ar's lifetime)
-            panic!("Failed to extract: {:?}", e);
-        }
-    };
-    return Ok(contents);
+/// This involves parsing the entire archive for each file extracted, so it's not very efficient.
+/// Use `Archive` to extract several files.
+pub fn extract_file(name: &str, archive_data: &[u8]) -> Result<Vec<u8>, Error> {
+    return Archive::new(archive_data)?.extract(name);
 }
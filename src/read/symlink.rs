@@ -0,0 +1,110 @@
+//! This module implements decoding of symbolic link entries.
+//!
+//! p7zip stores symlinks as regular streams containing the link target, with `S_IFLNK` set in the Unix mode.
+//! 7-Zip on Windows instead stores the raw reparse point data as the stream of an entry with
+//! `FILE_ATTRIBUTE_REPARSE_POINT` set.
+
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::convert::TryInto;
+
+const IO_REPARSE_TAG_MOUNT_POINT: u32 = 0xA000_0003;
+const IO_REPARSE_TAG_SYMLINK: u32 = 0xA000_000C;
+/// Prefix of NT object paths, as used in substitute names.
+const NT_PATH_PREFIX: &str = "\\??\\";
+
+fn u16_at(data: &[u8], offset: usize) -> Option<u16> {
+    return Some(u16::from_le_bytes(
+        data.get(offset..offset + 2)?.try_into().ok()?,
+    ));
+}
+
+fn u32_at(data: &[u8], offset: usize) -> Option<u32> {
+    return Some(u32::from_le_bytes(
+        data.get(offset..offset + 4)?.try_into().ok()?,
+    ));
+}
+
+/// Read a name of `len` bytes at `offset` from the path buffer as UTF-16.
+fn name_at(path_buffer: &[u8], offset: u16, len: u16) -> Option<String> {
+    let (offset, len) = (offset as usize, len as usize);
+    let bytes = path_buffer.get(offset..offset.checked_add(len)?)?;
+    let wide: Vec<u16> = bytes
+        .chunks_exact(2)
+        .map(|c| u16::from_le_bytes([c[0], c[1]]))
+        .collect();
+    return String::from_utf16(&wide).ok();
+}
+
+/// Extract the link target from a Windows reparse data buffer.
+///
+/// Only symbolic links and junctions are supported.
+/// The print name is preferred, as the substitute name is an NT object path.
+pub(crate) fn reparse_point_target(data: &[u8]) -> Option<String> {
+    let tag = u32_at(data, 0)?;
+    // Symlinks carry an additional flags field before the path buffer
+    let path_buffer_offset = match tag {
+        IO_REPARSE_TAG_SYMLINK => 20,
+        IO_REPARSE_TAG_MOUNT_POINT => 16,
+        _ => return None,
+    };
+    let substitute_offset = u16_at(data, 8)?;
+    let substitute_len = u16_at(data, 10)?;
+    let print_offset = u16_at(data, 12)?;
+    let print_len = u16_at(data, 14)?;
+    let path_buffer = data.get(path_buffer_offset..)?;
+
+    if print_len != 0 {
+        return name_at(path_buffer, print_offset, print_len);
+    }
+    let substitute = name_at(path_buffer, substitute_offset, substitute_len)?;
+    return Some(match substitute.strip_prefix(NT_PATH_PREFIX) {
+        Some(s) => String::from(s),
+        None => substitute,
+    });
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use alloc::vec;
+
+    /// Build a reparse data buffer for a symlink with the given names.
+    fn symlink_buffer(substitute: &str, print: &str) -> Vec<u8> {
+        let substitute: Vec<u8> = substitute
+            .encode_utf16()
+            .flat_map(u16::to_le_bytes)
+            .collect();
+        let print: Vec<u8> = print.encode_utf16().flat_map(u16::to_le_bytes).collect();
+        let mut data = vec![];
+        data.extend_from_slice(&IO_REPARSE_TAG_SYMLINK.to_le_bytes());
+        data.extend_from_slice(&((12 + substitute.len() + print.len()) as u16).to_le_bytes());
+        data.extend_from_slice(&[0, 0]);
+        data.extend_from_slice(&0u16.to_le_bytes());
+        data.extend_from_slice(&(substitute.len() as u16).to_le_bytes());
+        data.extend_from_slice(&(substitute.len() as u16).to_le_bytes());
+        data.extend_from_slice(&(print.len() as u16).to_le_bytes());
+        // SYMLINK_FLAG_RELATIVE
+        data.extend_from_slice(&1u32.to_le_bytes());
+        data.extend_from_slice(&substitute);
+        data.extend_from_slice(&print);
+        return data;
+    }
+
+    #[test]
+    fn reparse_point_target() {
+        let data = symlink_buffer("..\\target.txt", "..\\target.txt");
+        assert_eq!(
+            super::reparse_point_target(&data).unwrap(),
+            "..\\target.txt"
+        );
+
+        let data = symlink_buffer("\\??\\C:\\target", "");
+        assert_eq!(super::reparse_point_target(&data).unwrap(), "C:\\target");
+
+        // Truncated
+        assert_eq!(super::reparse_point_target(&data[..18]), None);
+        // Unsupported tag
+        assert_eq!(super::reparse_point_target(&[0; 20]), None);
+    }
+}
@@ -0,0 +1,82 @@
+//! This module implements decoding of the timestamps stored for each file.
+//!
+//! Timestamps are stored as Windows `FILETIME` values,
+//! which count 100-nanosecond intervals since 1601-01-01 00:00:00 UTC.
+
+/// Number of `FILETIME` intervals per second.
+const INTERVALS_PER_SECOND: u64 = 10_000_000;
+/// Seconds between 1601-01-01 and the Unix epoch.
+const UNIX_EPOCH_OFFSET_SECONDS: u64 = 11_644_473_600;
+
+/// A timestamp of a file contained within an archive.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct FileTime {
+    raw: u64,
+}
+
+impl FileTime {
+    /// Create a timestamp from it's raw `FILETIME` representation in the archive.
+    pub fn new(raw: u64) -> FileTime {
+        return FileTime { raw };
+    }
+
+    /// The raw `FILETIME` value, as stored in the archive.
+    pub fn raw(&self) -> u64 {
+        return self.raw;
+    }
+
+    /// Seconds and nanoseconds since the Unix epoch.
+    ///
+    /// The seconds are negative for timestamps before 1970, while the nanoseconds are always positive.
+    pub fn to_unix(&self) -> (i64, u32) {
+        let secs = (self.raw / INTERVALS_PER_SECOND) as i64 - UNIX_EPOCH_OFFSET_SECONDS as i64;
+        let nanos = (self.raw % INTERVALS_PER_SECOND) as u32 * 100;
+        return (secs, nanos);
+    }
+
+    /// Convert the timestamp to a `SystemTime`.
+    ///
+    /// Returns `None` if the platform can't represent it.
+    #[cfg(feature = "std")]
+    pub fn to_system_time(&self) -> Option<std::time::SystemTime> {
+        use std::time::{Duration, SystemTime};
+
+        let (secs, nanos) = self.to_unix();
+        if secs >= 0 {
+            return SystemTime::UNIX_EPOCH.checked_add(Duration::new(secs as u64, nanos));
+        }
+        return SystemTime::UNIX_EPOCH
+            .checked_sub(Duration::from_secs(secs.unsigned_abs()))?
+            .checked_add(Duration::from_nanos(nanos as u64));
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn to_unix() {
+        assert_eq!(FileTime::new(116_444_736_000_000_000).to_unix(), (0, 0));
+        // 2021-07-15 22:10:31.0000001 UTC
+        assert_eq!(
+            FileTime::new(132_708_606_310_000_001).to_unix(),
+            (1_626_387_031, 100)
+        );
+        assert_eq!(FileTime::new(0).to_unix(), (-11_644_473_600, 0));
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn to_system_time() {
+        use std::time::{Duration, SystemTime};
+
+        let t = FileTime::new(132_708_606_310_000_001).to_system_time();
+        assert_eq!(
+            t,
+            Some(SystemTime::UNIX_EPOCH + Duration::new(1_626_387_031, 100))
+        );
+        let t = FileTime::new(116_444_736_000_000_000 - 5).to_system_time();
+        assert_eq!(t, Some(SystemTime::UNIX_EPOCH - Duration::from_nanos(500)));
+    }
+}
@@ -0,0 +1,177 @@
+//! This module implements testing an archive's integrity, like `7z t` does.
+
+use super::decode;
+use super::err::Error;
+use super::name;
+use super::{InvalidNamePolicy, ReadOptions};
+
+use crate::codec::CodecError;
+use crate::parser::{parse_with_options, sevenz_crc, FileStreamInfo, FolderInfo};
+
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::convert::TryFrom;
+
+/// How an entry fared when verifying the archive.
+#[derive(Debug, Clone, PartialEq)]
+pub enum EntryStatus {
+    /// The contents match their CRC.
+    Ok,
+    /// The contents could be decoded, but the archive doesn't store a CRC to check them against.
+    NoCrc,
+    /// The entry has no contents to check, like directories, empty files and anti-items.
+    Empty,
+    /// The contents couldn't be decoded or don't match their CRC.
+    Failed(Error),
+}
+
+/// The result of verifying a single entry.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EntryReport {
+    /// The entry's name, with invalid UTF-16 escaped.
+    pub name: String,
+    pub status: EntryStatus,
+}
+
+/// A problem with a folder as a whole, such as a packed stream that doesn't match it's CRC.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FolderError {
+    /// Index of the folder, see `FileHandle::folder_index()`.
+    pub index: usize,
+    pub error: Error,
+}
+
+/// Summary of verifying an archive.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct VerifyReport {
+    /// All entries, in the order they're stored.
+    pub entries: Vec<EntryReport>,
+    pub folders: Vec<FolderError>,
+}
+
+impl VerifyReport {
+    /// Whether no problems were found.
+    pub fn is_ok(&self) -> bool {
+        return self.folders.is_empty()
+            && !self
+                .entries
+                .iter()
+                .any(|e| matches!(e.status, EntryStatus::Failed(_)));
+    }
+}
+
+/// Check the CRC of `data` against `expected`, if there is one.
+fn check_crc(data: &[u8], expected: Option<u32>) -> Result<(), Error> {
+    if let Some(expected) = expected {
+        let got = sevenz_crc(data);
+        if got != expected {
+            return Err(Error::Crc(expected, got));
+        }
+    }
+    return Ok(());
+}
+
+/// Check the CRCs of the folder's packed streams, adding any mismatches to `errors`.
+fn check_packed_streams(
+    archive_data: &[u8],
+    index: usize,
+    folder: &FolderInfo,
+    errors: &mut Vec<FolderError>,
+) {
+    for packed in &folder.packed_streams {
+        let data = usize::try_from(packed.offset)
+            .ok()
+            .zip(usize::try_from(packed.size).ok())
+            .and_then(|(offset, size)| archive_data.get(offset..offset.checked_add(size)?));
+        let res = match data {
+            Some(data) => check_crc(data, packed.crc),
+            // Decoding the folder will fail as well, so that's reported instead
+            None => Ok(()),
+        };
+        if let Err(error) = res {
+            errors.push(FolderError { index, error });
+        }
+    }
+}
+
+/// Check a file's contents, located in it's decoded folder's `folder_data`.
+fn check_stream(folder_data: &[u8], stream: &FileStreamInfo) -> EntryStatus {
+    let contents = usize::try_from(stream.offset)
+        .ok()
+        .zip(usize::try_from(stream.size).ok())
+        .and_then(|(offset, size)| folder_data.get(offset..offset.checked_add(size)?));
+    let contents = match contents {
+        Some(c) => c,
+        None => return EntryStatus::Failed(CodecError::InvalidData.into()),
+    };
+    return match (check_crc(contents, stream.crc), stream.crc) {
+        (Err(error), _) => EntryStatus::Failed(error),
+        (Ok(()), Some(_)) => EntryStatus::Ok,
+        (Ok(()), None) => EntryStatus::NoCrc,
+    };
+}
+
+/// Decode every folder of the archive and check all CRCs it stores,
+/// those of the packed streams, the decoded folders and each file.
+///
+/// Files aren't extracted, and each folder is only held in memory while it's checked.
+/// Only failing to parse the archive is an error, all other problems are listed in the report.
+pub fn verify(archive_data: &[u8]) -> Result<VerifyReport, Error> {
+    return verify_with_options(archive_data, &ReadOptions::default());
+}
+
+/// Like `verify()`, but with the given codecs and limits.
+///
+/// In recovery mode, damage to the headers is tolerated as described for `ReadOptions::recover`.
+pub fn verify_with_options(
+    archive_data: &[u8],
+    options: &ReadOptions,
+) -> Result<VerifyReport, Error> {
+    let ar = parse_with_options(archive_data, options, decode::decode_folder)?;
+    let mut report = VerifyReport::default();
+
+    // Group the files by folder, so that each folder is decoded once
+    let mut folder_files: Vec<Vec<usize>> = vec![vec![]; ar.folders.len()];
+    let mut statuses: Vec<EntryStatus> = vec![EntryStatus::Empty; ar.files.len()];
+    for (i, file) in ar.files.iter().enumerate() {
+        if let Some(stream) = &file.stream {
+            match folder_files.get_mut(stream.folder_index) {
+                Some(files) => files.push(i),
+                None => statuses[i] = EntryStatus::Failed(CodecError::InvalidData.into()),
+            }
+        }
+    }
+
+    for (index, (folder, files)) in ar.folders.iter().zip(folder_files).enumerate() {
+        check_packed_streams(archive_data, index, folder, &mut report.folders);
+        let decoded = decode::decode_folder(archive_data, folder, options)
+            .map_err(Error::from)
+            .and_then(|data| check_crc(&data, folder.crc).map(|_| data));
+        let data = match decoded {
+            Ok(data) => data,
+            Err(error) => {
+                for i in files {
+                    statuses[i] = EntryStatus::Failed(error.clone());
+                }
+                report.folders.push(FolderError { index, error });
+                continue;
+            }
+        };
+
+        for i in files {
+            if let Some(stream) = &ar.files[i].stream {
+                statuses[i] = check_stream(&data, stream);
+            }
+        }
+    }
+
+    for (file, status) in ar.files.iter().zip(statuses) {
+        report.entries.push(EntryReport {
+            // Escaping always succeeds
+            name: name::to_string(&file.name, InvalidNamePolicy::Escape).unwrap_or_default(),
+            status,
+        });
+    }
+    return Ok(report);
+}
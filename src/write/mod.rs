@@ -0,0 +1,291 @@
+//! This module implements an interface for writing 7zip archives.
+//!
+//! Contents are stored uncompressed using the Copy method by default,
+//! any other method needs an encoder in the codec registry, see `ArchiveWriter::method()`.
+
+use crate::codec::{CodecError, CodecRegistry};
+use crate::parser::{
+    sevenz_crc, write_archive, ArchiveProperties, Coder, CodersInfo, FilesInfo, FilesProperty,
+    Folder, Header, PackInfo, PropertyID, StreamsInfo, SubStreamsInfo,
+};
+use crate::read::{Attributes, FileTime};
+
+use alloc::string::String;
+use alloc::sync::Arc;
+use alloc::vec;
+use alloc::vec::Vec;
+use bitvec::prelude::*;
+use either::*;
+use widestring::U16String;
+
+const FILE_ATTRIBUTE_DIRECTORY: u32 = 0x0010;
+/// ID of the Copy method.
+const COPY_METHOD_ID: u8 = 0x00;
+
+/// An entry to be added to an archive.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NewEntry {
+    name: String,
+    /// `None` for directories.
+    contents: Option<Vec<u8>>,
+    modified: Option<FileTime>,
+    attributes: Option<Attributes>,
+    comment: Option<String>,
+}
+
+impl NewEntry {
+    /// A file with the given contents, which may be empty.
+    pub fn file(name: &str, contents: Vec<u8>) -> NewEntry {
+        return NewEntry {
+            name: String::from(name),
+            contents: Some(contents),
+            modified: None,
+            attributes: None,
+            comment: None,
+        };
+    }
+
+    /// A directory, with the directory attribute set.
+    pub fn directory(name: &str) -> NewEntry {
+        return NewEntry {
+            name: String::from(name),
+            contents: None,
+            modified: None,
+            attributes: Some(Attributes::new(FILE_ATTRIBUTE_DIRECTORY)),
+            comment: None,
+        };
+    }
+
+    pub fn with_modified(mut self, modified: FileTime) -> NewEntry {
+        self.modified = Some(modified);
+        return self;
+    }
+
+    pub fn with_attributes(mut self, attributes: Attributes) -> NewEntry {
+        self.attributes = Some(attributes);
+        return self;
+    }
+
+    pub fn with_comment(mut self, comment: &str) -> NewEntry {
+        self.comment = Some(String::from(comment));
+        return self;
+    }
+
+    /// The entry's contents, if it's a file which isn't empty.
+    fn stream(&self) -> Option<&[u8]> {
+        return self.contents.as_deref().filter(|c| !c.is_empty());
+    }
+}
+
+/// Builds an archive from entries added to it.
+///
+/// Every file is stored in full, even if it's contents are identical to another's,
+/// as the 7z format gives each file a substream of it's own.
+#[derive(Debug, Clone)]
+pub struct ArchiveWriter {
+    entries: Vec<NewEntry>,
+    solid: bool,
+    comment: Option<String>,
+    /// ID of the method used to encode the contents.
+    method: Vec<u8>,
+    /// The codecs providing the method's encoder.
+    registry: Arc<CodecRegistry>,
+}
+
+impl Default for ArchiveWriter {
+    fn default() -> Self {
+        return ArchiveWriter::new();
+    }
+}
+
+impl ArchiveWriter {
+    /// Create a writer for a solid archive using the Copy method.
+    pub fn new() -> ArchiveWriter {
+        return ArchiveWriter {
+            entries: vec![],
+            solid: true,
+            comment: None,
+            method: vec![COPY_METHOD_ID],
+            registry: Arc::new(CodecRegistry::new()),
+        };
+    }
+
+    /// Whether to store all contents in a single folder, or each file in it's own one.
+    pub fn solid(&mut self, solid: bool) -> &mut ArchiveWriter {
+        self.solid = solid;
+        return self;
+    }
+
+    /// Encode the contents using the method with the given ID, instead of the Copy method.
+    ///
+    /// The encoder is taken from the registry set with `ArchiveWriter::registry()`, or the built-in codecs.
+    pub fn method(&mut self, id: &[u8]) -> &mut ArchiveWriter {
+        self.method = Vec::from(id);
+        return self;
+    }
+
+    /// Set the codecs providing the encoder for the method set with `ArchiveWriter::method()`.
+    pub fn registry(&mut self, registry: Arc<CodecRegistry>) -> &mut ArchiveWriter {
+        self.registry = registry;
+        return self;
+    }
+
+    /// Set the archive's comment.
+    pub fn comment(&mut self, comment: &str) -> &mut ArchiveWriter {
+        self.comment = Some(String::from(comment));
+        return self;
+    }
+
+    pub fn add(&mut self, entry: NewEntry) -> &mut ArchiveWriter {
+        self.entries.push(entry);
+        return self;
+    }
+
+    /// Write the archive.
+    ///
+    /// Fails if the registry has no encoder for the chosen method, or encoding fails.
+    pub fn finish(&self) -> Result<Vec<u8>, CodecError> {
+        let encoder = self.registry.encoder(&self.method)?;
+        let streams: Vec<&[u8]> = self.entries.iter().filter_map(|e| e.stream()).collect();
+        let folders: Vec<&[&[u8]]> = match (streams.is_empty(), self.solid) {
+            (true, _) => vec![],
+            (false, true) => vec![&streams[..]],
+            (false, false) => streams.chunks(1).collect(),
+        };
+
+        let packed: Vec<Vec<u8>> = folders
+            .iter()
+            .map(|f| encoder.encode(&f.concat()))
+            .collect::<Result<_, _>>()?;
+        let main_streams = if folders.is_empty() {
+            None
+        } else {
+            let coder = Coder {
+                complex: None,
+                attrs: encoder.properties(),
+                id: self.method.clone(),
+            };
+            Some(streams_info(&folders, &packed, coder))
+        };
+        // Stored as null-terminated UTF-16LE
+        let archive_properties = self.comment.as_ref().map(|c| {
+            let comment: Vec<u8> = c
+                .encode_utf16()
+                .chain([0])
+                .flat_map(u16::to_le_bytes)
+                .collect();
+            ArchiveProperties {
+                property_data: vec![(PropertyID::Comment, comment)],
+                unknown_properties: vec![],
+            }
+        });
+        let header = Header {
+            archive_properties,
+            additional_streams: None,
+            main_streams,
+            files: Some(files_info(&self.entries)),
+        };
+        return Ok(write_archive(&packed.concat(), &header));
+    }
+}
+
+/// Describe folders which each store the given streams, packed into `packed` using the `coder`.
+fn streams_info(folders: &[&[&[u8]]], packed: &[Vec<u8>], coder: Coder) -> StreamsInfo {
+    let folder_sizes: Vec<u64> = folders
+        .iter()
+        .map(|f| f.iter().map(|s| s.len() as u64).sum())
+        .collect();
+    let num_streams: Vec<u64> = folders.iter().map(|f| f.len() as u64).collect();
+    // The last stream's size is implied by the folder's size
+    let unpack_sizes: Vec<u64> = folders
+        .iter()
+        .flat_map(|f| f[..f.len() - 1].iter().map(|s| s.len() as u64))
+        .collect();
+    let crcs: Vec<Option<u32>> = folders
+        .iter()
+        .flat_map(|f| f.iter().map(|s| Some(sevenz_crc(s))))
+        .collect();
+
+    let folder = Folder {
+        coders: vec![coder],
+        bind_pairs: vec![],
+        packed_streams_indices: None,
+    };
+    return StreamsInfo {
+        pack_info: Some(PackInfo {
+            pack_pos: 0,
+            num_pack_streams: folders.len(),
+            sizes: Some(packed.iter().map(|p| p.len() as u64).collect()),
+            crcs: None,
+        }),
+        coders_info: Some(CodersInfo {
+            num_folders: folders.len(),
+            folders_or_data_stream_index: Right(vec![folder; folders.len()]),
+            streams_unpack_sizes: folder_sizes,
+            folders_unpack_digests: None,
+        }),
+        substreams_info: Some(SubStreamsInfo {
+            num_unpack_streams_in_folders: num_streams
+                .iter()
+                .any(|n| *n != 1)
+                .then_some(num_streams),
+            unpack_sizes: (!unpack_sizes.is_empty()).then_some(unpack_sizes),
+            unknown_digests: Some(crcs),
+        }),
+    };
+}
+
+fn files_info(entries: &[NewEntry]) -> FilesInfo {
+    let mut properties = vec![];
+
+    let empty_stream: BitVec = entries.iter().map(|e| e.stream().is_none()).collect();
+    if empty_stream.any() {
+        let empty_file: BitVec = entries
+            .iter()
+            .filter(|e| e.stream().is_none())
+            .map(|e| e.contents.is_some())
+            .collect();
+        properties.push(FilesProperty::EmptyStream(empty_stream));
+        if empty_file.any() {
+            properties.push(FilesProperty::EmptyFile(empty_file));
+        }
+    }
+
+    properties.push(FilesProperty::Names(
+        entries
+            .iter()
+            .map(|e| Right(U16String::from_str(&e.name)))
+            .collect(),
+    ));
+
+    if entries.iter().any(|e| e.comment.is_some()) {
+        properties.push(FilesProperty::Comments(
+            entries
+                .iter()
+                .map(|e| e.comment.as_deref().map(|c| Right(U16String::from_str(c))))
+                .collect(),
+        ));
+    }
+
+    if entries.iter().any(|e| e.modified.is_some()) {
+        properties.push(FilesProperty::MTime(
+            entries
+                .iter()
+                .map(|e| e.modified.map(|t| Right(t.raw())))
+                .collect(),
+        ));
+    }
+    if entries.iter().any(|e| e.attributes.is_some()) {
+        properties.push(FilesProperty::Attributes(
+            entries
+                .iter()
+                .map(|e| e.attributes.map(|a| Right(a.raw())))
+                .collect(),
+        ));
+    }
+
+    return FilesInfo {
+        num_files: entries.len(),
+        properties,
+    };
+}
@@ -0,0 +1,166 @@
+use sevenz::read::{
+    extract_to_dir_with_options, Archive, Codec, CodecError, CodecRegistry, Error, FolderCache,
+    ReadOptions,
+};
+use sevenz::write::{ArchiveWriter, NewEntry};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+const KINDS_TEST_ARCHIVE: &[u8] = include_bytes!("../testdata/test-kinds.7z");
+const COMMENTS_TEST_ARCHIVE: &[u8] = include_bytes!("../testdata/test-comments.7z");
+
+#[test]
+fn lookup_by_name_and_index() {
+    let archive = Archive::new(KINDS_TEST_ARCHIVE).unwrap();
+    assert_eq!(archive.len(), 5);
+    assert_eq!(archive.index_of("dir/empty.txt"), Some(2));
+    assert_eq!(archive.by_index(1).unwrap().name(), "dir");
+    assert!(archive.by_index(5).is_none());
+    assert!(archive.by_name("missing.txt").is_none());
+
+    let file = archive.by_name("dir/file.txt").unwrap();
+    assert_eq!(file.extract_contents_vec().unwrap(), b"Hello, world!\n");
+    assert_eq!(archive.extract("dir/empty.txt").unwrap(), b"");
+    assert!(matches!(
+        archive.extract("missing.txt"),
+        Err(Error::NoSuchFileName(n)) if n == "missing.txt"
+    ));
+
+    let names: Vec<String> = archive.iter().map(|f| f.name()).collect();
+    assert_eq!(names.len(), 5);
+    assert_eq!(names[3], "removed.txt");
+
+    let archive = Archive::new(COMMENTS_TEST_ARCHIVE).unwrap();
+    assert_eq!(archive.comment().as_deref(), Some("Archive comment"));
+    assert!(archive.info().solid);
+}
+
+#[test]
+fn select_by_glob() {
+    let archive = Archive::new(KINDS_TEST_ARCHIVE).unwrap();
+    let names =
+        |pattern| -> Vec<String> { archive.glob(pattern).iter().map(|f| f.name()).collect() };
+    assert_eq!(names("dir/*.txt"), vec!["dir/file.txt", "dir/empty.txt"]);
+    assert_eq!(names("*.txt"), vec!["removed.txt"]);
+    assert_eq!(
+        names("**/*.txt"),
+        vec!["dir/file.txt", "dir/empty.txt", "removed.txt"]
+    );
+    assert_eq!(names("removed?dir"), vec!["removed_dir"]);
+    assert!(names("*.bin").is_empty());
+}
+
+/// Stands in for the Copy method, counting how often a folder is decoded.
+struct CountingCopy {
+    decoded: Arc<AtomicUsize>,
+}
+
+impl Codec for CountingCopy {
+    fn decode(&self, data: &[u8]) -> Result<Vec<u8>, CodecError> {
+        self.decoded.fetch_add(1, Ordering::SeqCst);
+        Ok(data.to_vec())
+    }
+}
+
+/// Options decoding the Copy method with `CountingCopy`, and the counter it increments.
+fn counting_options() -> (ReadOptions, Arc<AtomicUsize>) {
+    let decoded = Arc::new(AtomicUsize::new(0));
+    let counter = decoded.clone();
+    let mut registry = CodecRegistry::new();
+    registry.register(&[0x00], move |_: Option<&[u8]>| {
+        Ok(Box::new(CountingCopy {
+            decoded: counter.clone(),
+        }) as Box<dyn Codec>)
+    });
+    let options = ReadOptions {
+        registry: Arc::new(registry),
+        ..Default::default()
+    };
+    (options, decoded)
+}
+
+/// An archive with three files, all in one folder if `solid`, or each in their own.
+fn three_files(solid: bool) -> Vec<u8> {
+    let mut writer = ArchiveWriter::new();
+    writer.solid(solid);
+    writer.add(NewEntry::file("a.txt", b"first\n".to_vec()));
+    writer.add(NewEntry::directory("dir"));
+    writer.add(NewEntry::file("dir/b.txt", b"second\n".to_vec()));
+    writer.add(NewEntry::file("c.txt", b"third\n".to_vec()));
+    writer.finish().unwrap()
+}
+
+#[test]
+fn iterating_decodes_each_folder_once() {
+    for solid in [true, false] {
+        let data = three_files(solid);
+        let (options, decoded) = counting_options();
+        let archive = Archive::with_options(&data, &options).unwrap();
+        let folders = if solid { 1 } else { 3 };
+
+        for file in archive.iter() {
+            file.extract_contents_vec().unwrap();
+        }
+        assert_eq!(decoded.swap(0, Ordering::SeqCst), 3);
+
+        let contents: Vec<(String, Vec<u8>)> = archive
+            .contents()
+            .map(|(f, c)| (f.name(), c.unwrap()))
+            .collect();
+        assert_eq!(decoded.swap(0, Ordering::SeqCst), folders);
+        assert_eq!(contents[1], (String::from("dir"), vec![]));
+        assert_eq!(
+            contents[2],
+            (String::from("dir/b.txt"), b"second\n".to_vec())
+        );
+
+        let dest = std::env::temp_dir().join(format!(
+            "sevenz-test-decode-once-{}-{}",
+            solid,
+            std::process::id()
+        ));
+        extract_to_dir_with_options(&data, &dest, &options).unwrap();
+        assert_eq!(decoded.swap(0, Ordering::SeqCst), folders);
+        assert_eq!(std::fs::read(dest.join("c.txt")).unwrap(), b"third\n");
+        std::fs::remove_dir_all(&dest).unwrap();
+    }
+}
+
+#[test]
+fn folder_cache() {
+    let data = three_files(false);
+    let other_data = three_files(false);
+    let (options, decoded) = counting_options();
+    let archive = Archive::with_options(&data, &options).unwrap();
+    fn extract<'a>(archive: &Archive<'a>, cache: &mut FolderCache<'a>, index: usize) -> Vec<u8> {
+        let file = archive.by_index(index).unwrap();
+        file.extract_contents_cached(cache).unwrap()
+    }
+
+    // Only the most recently used folder is kept without room for others
+    let mut cache = FolderCache::new(0);
+    assert_eq!(extract(&archive, &mut cache, 3), b"third\n");
+    assert_eq!(extract(&archive, &mut cache, 3), b"third\n");
+    assert_eq!(extract(&archive, &mut cache, 0), b"first\n");
+    assert_eq!(extract(&archive, &mut cache, 3), b"third\n");
+    assert_eq!(decoded.swap(0, Ordering::SeqCst), 3);
+    assert_eq!(cache.size(), 6);
+
+    // Up to 13 bytes fit the first two folders, but not the third as well
+    let mut cache = FolderCache::new(13);
+    for index in [0, 2, 0, 2, 3, 0] {
+        extract(&archive, &mut cache, index);
+    }
+    assert_eq!(decoded.swap(0, Ordering::SeqCst), 4);
+    assert_eq!(cache.size(), 12);
+
+    // The cache is emptied when used with another archive
+    let other = Archive::with_options(&other_data, &options).unwrap();
+    let file = other.by_index(0).unwrap();
+    assert_eq!(
+        file.extract_contents_cached(&mut cache).unwrap(),
+        b"first\n"
+    );
+    assert_eq!(decoded.swap(0, Ordering::SeqCst), 1);
+    assert_eq!(cache.size(), 6);
+}
@@ -0,0 +1,305 @@
+#![cfg(feature = "std")]
+
+use sevenz::read::{
+    extract_to_dir, extract_to_dir_with_options, DamagedEntry, Error, ReadOptions, SkipReason,
+    UnsafePathReason,
+};
+use std::fs;
+
+const TRAVERSAL_TEST_ARCHIVE: &[u8] = include_bytes!("../testdata/test-traversal.7z");
+
+#[test]
+fn extract_to_dir_skips_unsafe_paths() {
+    let root = std::env::temp_dir().join(format!("sevenz-test-traversal-{}", std::process::id()));
+    let dest = root.join("out");
+    let _ = fs::remove_dir_all(&root);
+
+    let report = extract_to_dir(TRAVERSAL_TEST_ARCHIVE, &dest).unwrap();
+
+    assert_eq!(
+        fs::read(dest.join("ok").join("nested.txt")).unwrap(),
+        b"nested\n"
+    );
+    assert_eq!(
+        fs::read(dest.join("dir").join("plain.txt")).unwrap(),
+        b"plain\n"
+    );
+    assert!(dest.join("dir").is_dir());
+    assert_eq!(report.extracted.len(), 3);
+    assert!(!root.join("evil.txt").exists());
+    assert!(!root.join("b.txt").exists());
+
+    let skipped: Vec<(&str, SkipReason)> = report
+        .skipped
+        .iter()
+        .map(|s| (s.name.as_str(), s.reason))
+        .collect();
+    assert_eq!(
+        skipped,
+        vec![
+            (
+                "../evil.txt",
+                SkipReason::UnsafePath(UnsafePathReason::ParentDirectory)
+            ),
+            (
+                "/abs.txt",
+                SkipReason::UnsafePath(UnsafePathReason::Absolute)
+            ),
+            (
+                "C:\\drive.txt",
+                SkipReason::UnsafePath(UnsafePathReason::DriveLetter)
+            ),
+            (
+                "a\\..\\..\\b.txt",
+                SkipReason::UnsafePath(UnsafePathReason::ParentDirectory)
+            ),
+            ("gone.txt", SkipReason::AntiItem),
+        ]
+    );
+
+    fs::remove_dir_all(&root).unwrap();
+}
+
+const METADATA_TEST_ARCHIVE: &[u8] = include_bytes!("../testdata/test-metadata.7z");
+
+#[test]
+fn extract_to_dir_restores_metadata() {
+    use std::time::{Duration, SystemTime};
+
+    let root = std::env::temp_dir().join(format!("sevenz-test-metadata-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&root);
+
+    extract_to_dir(METADATA_TEST_ARCHIVE, &root).unwrap();
+
+    let files = sevenz::read::list_files(METADATA_TEST_ARCHIVE).unwrap();
+    let mtime = files[0].modified().unwrap();
+    assert_eq!(mtime.to_unix(), (1_626_387_031, 0));
+    let mtime = SystemTime::UNIX_EPOCH + Duration::from_secs(1_626_387_031);
+
+    let script = fs::metadata(root.join("bin").join("run.sh")).unwrap();
+    assert_eq!(script.modified().unwrap(), mtime);
+    let read_only = fs::metadata(root.join("readonly.txt")).unwrap();
+    assert_eq!(
+        read_only.modified().unwrap(),
+        mtime + Duration::from_secs(1)
+    );
+    assert!(read_only.permissions().readonly());
+    let dir = fs::metadata(root.join("bin")).unwrap();
+    assert_eq!(dir.modified().unwrap(), mtime + Duration::from_secs(2));
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        assert_eq!(script.permissions().mode() & 0o7777, 0o755);
+        assert_eq!(dir.permissions().mode() & 0o7777, 0o750);
+    }
+
+    let mut perms = read_only.permissions();
+    #[allow(clippy::permissions_set_readonly_false)]
+    perms.set_readonly(false);
+    fs::set_permissions(root.join("readonly.txt"), perms).unwrap();
+    fs::remove_dir_all(&root).unwrap();
+}
+
+const SYMLINKS_TEST_ARCHIVE: &[u8] = include_bytes!("../testdata/test-symlinks.7z");
+
+#[test]
+fn list_symlinks() {
+    let files = sevenz::read::list_files(SYMLINKS_TEST_ARCHIVE).unwrap();
+    let links: Vec<(String, Option<String>)> = files
+        .iter()
+        .map(|f| (f.name(), f.symlink_target().unwrap()))
+        .collect();
+    let expected = [
+        ("target.txt", None),
+        ("link.txt", Some("target.txt")),
+        ("sub/up.txt", Some("../target.txt")),
+        ("sub", None),
+        ("escape", Some("sub/../../outside")),
+        ("absolute", Some("/etc/passwd")),
+        ("winlink.txt", Some("sub\\..\\target.txt")),
+    ];
+    assert_eq!(links.len(), expected.len());
+    for ((name, target), (expected_name, expected_target)) in links.iter().zip(expected) {
+        assert_eq!(name, expected_name);
+        assert_eq!(target.as_deref(), expected_target);
+    }
+}
+
+#[cfg(unix)]
+#[test]
+fn extract_to_dir_creates_symlinks() {
+    use std::path::Path;
+
+    let root = std::env::temp_dir().join(format!("sevenz-test-symlinks-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&root);
+
+    let report = extract_to_dir(SYMLINKS_TEST_ARCHIVE, &root).unwrap();
+
+    assert_eq!(
+        fs::read_link(root.join("link.txt")).unwrap(),
+        Path::new("target.txt")
+    );
+    assert_eq!(fs::read(root.join("link.txt")).unwrap(), b"target\n");
+    assert_eq!(
+        fs::read(root.join("sub").join("up.txt")).unwrap(),
+        b"target\n"
+    );
+    assert_eq!(
+        fs::read_link(root.join("winlink.txt")).unwrap(),
+        Path::new("sub/../target.txt")
+    );
+    assert!(fs::symlink_metadata(root.join("escape")).is_err());
+    assert!(fs::symlink_metadata(root.join("absolute")).is_err());
+
+    let skipped: Vec<(&str, SkipReason)> = report
+        .skipped
+        .iter()
+        .map(|s| (s.name.as_str(), s.reason))
+        .collect();
+    assert_eq!(
+        skipped,
+        vec![
+            ("escape", SkipReason::UnsafeSymlinkTarget),
+            ("absolute", SkipReason::UnsafeSymlinkTarget),
+        ]
+    );
+
+    fs::remove_dir_all(&root).unwrap();
+}
+
+#[cfg(unix)]
+#[test]
+fn extract_to_dir_refuses_chained_symlinks() {
+    use sevenz::read::Attributes;
+    use sevenz::write::{ArchiveWriter, NewEntry};
+
+    fn link(name: &str, target: &str) -> NewEntry {
+        // Unix extension with S_IFLNK
+        NewEntry::file(name, target.as_bytes().to_vec())
+            .with_attributes(Attributes::new(0x8000 | (0o120777 << 16)))
+    }
+
+    use std::path::Path;
+
+    let root = std::env::temp_dir().join(format!("sevenz-test-chained-{}", std::process::id()));
+    let dest = root.join("out");
+    let _ = fs::remove_dir_all(&root);
+    let mut writer = ArchiveWriter::new();
+    writer.add(link("up", "."));
+    // Each stays inside by itself, but is really created at `x` pointing outside
+    writer.add(link("up/x", ".."));
+    writer.add(link("up/sub/y", "."));
+    writer.add(NewEntry::directory("dir"));
+    writer.add(link("dir", "."));
+
+    let report = extract_to_dir(&writer.finish().unwrap(), &dest).unwrap();
+
+    assert_eq!(fs::read_link(dest.join("up")).unwrap(), Path::new("."));
+    assert!(fs::symlink_metadata(dest.join("x")).is_err());
+    assert!(fs::symlink_metadata(dest.join("sub")).is_err());
+    assert!(fs::symlink_metadata(dest.join("dir")).unwrap().is_dir());
+    let skipped: Vec<(&str, SkipReason)> = report
+        .skipped
+        .iter()
+        .map(|s| (s.name.as_str(), s.reason))
+        .collect();
+    assert_eq!(
+        skipped,
+        vec![
+            ("up/x", SkipReason::UnsafeSymlinkTarget),
+            ("up/sub/y", SkipReason::UnsafeSymlinkTarget),
+            ("dir", SkipReason::DirectoryExists),
+        ]
+    );
+
+    fs::remove_dir_all(&root).unwrap();
+}
+
+#[cfg(unix)]
+#[test]
+fn extract_to_dir_follows_existing_symlinks() {
+    use sevenz::read::Attributes;
+    use sevenz::write::{ArchiveWriter, NewEntry};
+
+    fn link(name: &str, target: &str) -> NewEntry {
+        // Unix extension with S_IFLNK
+        NewEntry::file(name, target.as_bytes().to_vec())
+            .with_attributes(Attributes::new(0x8000 | (0o120777 << 16)))
+    }
+
+    use std::path::Path;
+
+    let root = std::env::temp_dir().join(format!("sevenz-test-follow-{}", std::process::id()));
+    let dest = root.join("out");
+    let _ = fs::remove_dir_all(&root);
+    let mut writer = ArchiveWriter::new();
+    // `e` only leads outside through `d`, which exists by the time it's created
+    writer.add(link("d", "."));
+    writer.add(link("e", "d/.."));
+    // `f` only leads outside through `g`, which is created after it
+    writer.add(link("f", "g/.."));
+    writer.add(link("g", "."));
+
+    let report = extract_to_dir(&writer.finish().unwrap(), &dest).unwrap();
+
+    assert_eq!(fs::read_link(dest.join("d")).unwrap(), Path::new("."));
+    assert_eq!(fs::read_link(dest.join("g")).unwrap(), Path::new("."));
+    assert!(fs::symlink_metadata(dest.join("e")).is_err());
+    assert!(fs::symlink_metadata(dest.join("f")).is_err());
+    assert_eq!(report.extracted, vec![dest.join("d"), dest.join("g")]);
+    let skipped: Vec<(&str, SkipReason)> = report
+        .skipped
+        .iter()
+        .map(|s| (s.name.as_str(), s.reason))
+        .collect();
+    assert_eq!(
+        skipped,
+        vec![
+            ("e", SkipReason::UnsafeSymlinkTarget),
+            ("f", SkipReason::UnsafeSymlinkTarget),
+        ]
+    );
+
+    fs::remove_dir_all(&root).unwrap();
+}
+
+const UNCOMPRESSED_TEST_ARCHIVE: &[u8] = include_bytes!("../testdata/test-uncompressed.txt.7z");
+
+#[test]
+fn extract_to_dir_reports_damaged_entries() {
+    let root = std::env::temp_dir().join(format!("sevenz-test-damaged-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&root);
+    let options = ReadOptions {
+        recover: true,
+        ..Default::default()
+    };
+
+    // Damaged contents fail the extraction, unless recovering
+    let mut data = UNCOMPRESSED_TEST_ARCHIVE.to_vec();
+    data[40] ^= 0x01;
+    assert!(matches!(extract_to_dir(&data, &root), Err(Error::Crc(..))));
+    let report = extract_to_dir_with_options(&data, &root, &options).unwrap();
+    assert_eq!(report.extracted, vec![root.join("test.txt")]);
+    assert!(matches!(
+        &report.damaged[..],
+        [DamagedEntry {
+            error: Error::Crc(..),
+            ..
+        }]
+    ));
+
+    // Contents which can't be decoded aren't written at all
+    fs::remove_dir_all(&root).unwrap();
+    let mut data = UNCOMPRESSED_TEST_ARCHIVE.to_vec();
+    data[32] = 0x00;
+    assert!(extract_to_dir(&data, &root).is_err());
+    fs::remove_dir_all(&root).unwrap();
+    let report = extract_to_dir_with_options(&data, &root, &options).unwrap();
+    assert!(report.extracted.is_empty());
+    assert_eq!(report.damaged[0].name, "test.txt");
+    assert!(matches!(report.damaged[0].error, Error::CodecFailed(_)));
+    assert!(!root.join("test.txt").exists());
+
+    fs::remove_dir_all(&root).unwrap();
+}
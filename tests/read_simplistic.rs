@@ -1,5 +1,3 @@
-use sevenz;
-
 const UNCOMPRESSED_TEST_ARCHIVE: &[u8] = include_bytes!("../testdata/test-uncompressed.txt.7z");
 const TEST_TXT_CONTENTS: &[u8] = include_bytes!("../testdata/test-uncompressed.txt");
 
@@ -8,3 +6,421 @@ fn unpack_single_uncompressed_file() {
     let res = sevenz::read::extract_file("test.txt", UNCOMPRESSED_TEST_ARCHIVE).unwrap();
     assert_eq!(res, TEST_TXT_CONTENTS);
 }
+
+#[test]
+fn list_single_uncompressed_file_attributes() {
+    let files = sevenz::read::list_files(UNCOMPRESSED_TEST_ARCHIVE).unwrap();
+    assert_eq!(files.len(), 1);
+    let attrs = files[0].attributes().unwrap();
+    assert!(attrs.is_archive());
+    assert!(!attrs.is_directory());
+    assert!(!attrs.is_read_only());
+    assert_eq!(attrs.unix_mode(), Some(0o644));
+    assert_eq!(
+        attrs.unix_file_type(),
+        Some(sevenz::read::UnixFileType::Regular)
+    );
+}
+
+const KINDS_TEST_ARCHIVE: &[u8] = include_bytes!("../testdata/test-kinds.7z");
+
+#[test]
+fn list_entry_kinds() {
+    use sevenz::read::EntryKind;
+
+    let files = sevenz::read::list_files(KINDS_TEST_ARCHIVE).unwrap();
+    let kinds: Vec<(String, EntryKind)> = files.iter().map(|f| (f.name(), f.kind())).collect();
+    assert_eq!(
+        kinds,
+        vec![
+            (String::from("dir/file.txt"), EntryKind::File),
+            (String::from("dir"), EntryKind::Directory),
+            (String::from("dir/empty.txt"), EntryKind::EmptyFile),
+            (String::from("removed.txt"), EntryKind::AntiFile),
+            (String::from("removed_dir"), EntryKind::AntiDirectory),
+        ]
+    );
+    assert!(files[1].attributes().unwrap().is_directory());
+}
+
+#[test]
+fn unpack_empty_file() {
+    let res = sevenz::read::extract_file("dir/empty.txt", KINDS_TEST_ARCHIVE).unwrap();
+    assert!(res.is_empty());
+    let res = sevenz::read::extract_file("dir/file.txt", KINDS_TEST_ARCHIVE).unwrap();
+    assert_eq!(res, b"Hello, world!\n");
+}
+
+const EXTERNAL_TEST_ARCHIVE: &[u8] = include_bytes!("../testdata/test-external.7z");
+
+#[test]
+fn list_externally_stored_properties() {
+    let files = sevenz::read::list_files(EXTERNAL_TEST_ARCHIVE).unwrap();
+    let names: Vec<String> = files.iter().map(|f| f.name()).collect();
+    assert_eq!(names, vec!["first.txt", "second.txt"]);
+    assert_eq!(files[0].attributes().unwrap().unix_mode(), Some(0o644));
+    assert_eq!(files[1].attributes().unwrap().unix_mode(), Some(0o755));
+
+    let res = sevenz::read::extract_file("second.txt", EXTERNAL_TEST_ARCHIVE).unwrap();
+    assert_eq!(res, b"Second file\n");
+}
+
+const INVALID_NAME_TEST_ARCHIVE: &[u8] = include_bytes!("../testdata/test-invalid-name.7z");
+
+#[test]
+fn list_invalid_utf16_name() {
+    use sevenz::read::InvalidNamePolicy;
+
+    let files = sevenz::read::list_files(INVALID_NAME_TEST_ARCHIVE).unwrap();
+    assert_eq!(files[0].name(), "bad\u{FFFD}.txt");
+    assert_eq!(
+        files[0]
+            .name_with_policy(InvalidNamePolicy::Escape)
+            .unwrap(),
+        "bad%uD800.txt"
+    );
+    assert!(files[0].name_with_policy(InvalidNamePolicy::Error).is_err());
+    assert_eq!(
+        files[0].name_wide().as_slice(),
+        &[0x62, 0x61, 0x64, 0xD800, 0x2E, 0x74, 0x78, 0x74]
+    );
+    assert_eq!(
+        files[1].name_with_policy(InvalidNamePolicy::Error).unwrap(),
+        "good.txt"
+    );
+    assert_eq!(files[0].extract_contents_vec().unwrap(), b"Invalid name\n");
+}
+
+const COMMENTS_TEST_ARCHIVE: &[u8] = include_bytes!("../testdata/test-comments.7z");
+
+#[test]
+fn list_externally_stored_comments() {
+    let files = sevenz::read::list_files(COMMENTS_TEST_ARCHIVE).unwrap();
+    assert_eq!(files[0].comment().as_deref(), Some("The first file"));
+    assert_eq!(files[1].comment(), None);
+    assert_eq!(files[1].extract_contents_vec().unwrap(), b"second\n");
+
+    let comment = sevenz::read::archive_comment(COMMENTS_TEST_ARCHIVE).unwrap();
+    assert_eq!(comment.as_deref(), Some("Archive comment"));
+    let comment = sevenz::read::archive_comment(UNCOMPRESSED_TEST_ARCHIVE).unwrap();
+    assert_eq!(comment, None);
+}
+
+const STARTPOS_TEST_ARCHIVE: &[u8] = include_bytes!("../testdata/test-startpos.7z");
+
+#[test]
+fn list_start_positions() {
+    let files = sevenz::read::list_files(STARTPOS_TEST_ARCHIVE).unwrap();
+    assert_eq!(files[0].start_pos(), None);
+    assert_eq!(files[1].start_pos(), Some(4096));
+    assert_eq!(files[1].extract_contents_vec().unwrap(), b"part\n");
+}
+
+#[test]
+fn archive_info() {
+    let info = sevenz::read::archive_info(UNCOMPRESSED_TEST_ARCHIVE).unwrap();
+    assert_eq!(info.version, (0, 4));
+    assert_eq!(info.header_offset, 32 + 19);
+    assert_eq!(info.header_size, 90);
+    assert_eq!(info.physical_size, UNCOMPRESSED_TEST_ARCHIVE.len() as u64);
+    assert_eq!(info.num_folders, 1);
+    assert_eq!(info.num_pack_streams, 1);
+    assert_eq!(info.packed_size, 19);
+    assert_eq!(info.unpacked_size, 15);
+    assert!(!info.solid);
+    assert!(!info.encoded_header);
+    assert!(!info.encrypted);
+    assert_eq!(info.methods, vec!["LZMA2:12"]);
+
+    let info = sevenz::read::archive_info(KINDS_TEST_ARCHIVE).unwrap();
+    assert_eq!(info.num_folders, 1);
+    assert!(!info.solid);
+    assert_eq!(info.methods, vec!["Copy"]);
+
+    let info = sevenz::read::archive_info(COMMENTS_TEST_ARCHIVE).unwrap();
+    assert!(info.solid);
+    assert_eq!(info.unpacked_size, 13);
+}
+
+#[test]
+fn list_sizes_and_methods() {
+    let files = sevenz::read::list_files(UNCOMPRESSED_TEST_ARCHIVE).unwrap();
+    assert_eq!(files[0].size(), 15);
+    assert_eq!(files[0].packed_size(), 19);
+    assert_eq!(files[0].crc(), Some(4206898572));
+    assert_eq!(files[0].folder_index(), Some(0));
+    assert_eq!(files[0].method().as_deref(), Some("LZMA2:12"));
+    assert_eq!(
+        files[0].methods(),
+        vec![sevenz::read::Method::Lzma2(Some(
+            sevenz::read::Lzma2Properties { dict_size: 4096 }
+        ))]
+    );
+
+    // Solid, with "first\n" and "second\n" sharing a folder
+    let files = sevenz::read::list_files(COMMENTS_TEST_ARCHIVE).unwrap();
+    assert_eq!(files[0].size(), 6);
+    assert_eq!(files[1].size(), 7);
+    assert_eq!(files[0].packed_size(), 6);
+    assert_eq!(files[1].folder_index(), Some(0));
+    assert_eq!(files[1].method().as_deref(), Some("Copy"));
+
+    let files = sevenz::read::list_files(KINDS_TEST_ARCHIVE).unwrap();
+    let dir = files
+        .iter()
+        .find(|f| f.kind() == sevenz::read::EntryKind::Directory)
+        .unwrap();
+    assert_eq!(dir.size(), 0);
+    assert_eq!(dir.packed_size(), 0);
+    assert_eq!(dir.crc(), None);
+    assert_eq!(dir.folder_index(), None);
+    assert_eq!(dir.method(), None);
+}
+
+/// Stands in for a private method, replacing the built-in LZMA2 decoder.
+struct Shout {}
+
+impl sevenz::read::Codec for Shout {
+    fn decode(&self, data: &[u8]) -> Result<Vec<u8>, sevenz::read::CodecError> {
+        Ok(data[3..data.len() - 1].to_ascii_uppercase())
+    }
+}
+
+#[test]
+fn list_with_custom_codec() {
+    use sevenz::read::{Codec, CodecError, CodecRegistry, Error};
+    use std::sync::Arc;
+
+    let mut registry = CodecRegistry::new();
+    registry.register(&[0x21], |_: Option<&[u8]>| {
+        Ok(Box::new(Shout {}) as Box<dyn Codec>)
+    });
+    let files =
+        sevenz::read::list_files_with_registry(UNCOMPRESSED_TEST_ARCHIVE, Arc::new(registry))
+            .unwrap();
+    assert_eq!(
+        files[0].extract_contents_vec().unwrap(),
+        TEST_TXT_CONTENTS.to_ascii_uppercase()
+    );
+
+    let files = sevenz::read::list_files_with_registry(
+        UNCOMPRESSED_TEST_ARCHIVE,
+        Arc::new(CodecRegistry::empty()),
+    )
+    .unwrap();
+    assert!(matches!(
+        files[0].extract_contents_vec(),
+        Err(Error::CodecFailed(CodecError::UnsupportedMethod(_)))
+    ));
+}
+
+#[test]
+fn corrupt_archive_is_an_error() {
+    // A folder without coders
+    let mut data = UNCOMPRESSED_TEST_ARCHIVE.to_vec();
+    data[63] = 0;
+    assert!(sevenz::read::list_files(&data).is_err());
+    assert!(sevenz::read::extract_file("test.txt", &data).is_err());
+}
+
+#[test]
+fn errors_dont_borrow_the_archive() {
+    use sevenz::read::{Error, SevenZParserErrorKind};
+
+    fn assert_owned<T: std::error::Error + Send + Sync + 'static>(_: &T) {}
+
+    let err = {
+        let mut data = UNCOMPRESSED_TEST_ARCHIVE.to_vec();
+        data[63] = 0;
+        sevenz::read::archive_info(&data).unwrap_err()
+    };
+    assert_owned(&err);
+    let Error::Parser(e) = &err else {
+        panic!("unexpected error {:?}", err);
+    };
+    assert_eq!(e.kind, SevenZParserErrorKind::InvalidStreamsInfo);
+    assert_eq!(e.offset(), Some(63));
+    assert_eq!(
+        err.to_string(),
+        "invalid archive: inconsistent streams info at offset 63 \
+         (in archive header > header main_streams > streams_info coders_info > coders_info folders > entry 0)"
+    );
+}
+
+#[test]
+fn error_diagnostic() {
+    use sevenz::read::{Error, SevenZParserErrorKind};
+
+    // The header's property ID
+    let mut data = UNCOMPRESSED_TEST_ARCHIVE.to_vec();
+    data[51] = 0x02;
+    let Err(Error::Parser(e)) = sevenz::read::archive_info(&data) else {
+        panic!("expected a parser error");
+    };
+    assert_eq!(
+        e.kind,
+        SevenZParserErrorKind::Unexpected {
+            expected: vec![0x01],
+            found: vec![0x02]
+        }
+    );
+    assert_eq!(e.offset(), Some(51));
+    assert_eq!(
+        e.diagnostic(&data),
+        "\
+error: expected 01 but found 02
+  --> offset 51 (0x33)
+  00000020 | 01 00 0e 54 68 69 73 20 69 73 20 61 20 74 65 73
+  00000030 | 74 0a 00 02 04 06 00 01 09 13 00 07 0b 01 00 01
+           |          ^^
+  in archive header at offset 51
+    in header PropertyID::Header at offset 51
+"
+    );
+}
+
+#[test]
+fn recovery() {
+    use sevenz::read::{archive_info_with_options, list_files_with_options, ReadOptions, Warning};
+
+    let options = ReadOptions {
+        recover: true,
+        ..Default::default()
+    };
+
+    // Wrong header CRC, which also breaks the start header CRC
+    let mut data = UNCOMPRESSED_TEST_ARCHIVE.to_vec();
+    data[28] ^= 0xFF;
+    assert!(sevenz::read::archive_info(&data).is_err());
+    let info = archive_info_with_options(&data, &options).unwrap();
+    assert!(matches!(
+        info.warnings[..],
+        [Warning::StartHeaderCrc(..), Warning::HeaderCrc(..)]
+    ));
+    let files = list_files_with_options(&data, &options).unwrap();
+    assert_eq!(files[0].extract_contents_vec().unwrap(), TEST_TXT_CONTENTS);
+
+    // Unusable start header, so the header has to be searched for
+    let mut data = UNCOMPRESSED_TEST_ARCHIVE.to_vec();
+    data[12..32].fill(0);
+    assert!(sevenz::read::archive_info(&data).is_err());
+    let info = archive_info_with_options(&data, &options).unwrap();
+    assert!(matches!(
+        info.warnings[..],
+        [Warning::StartHeaderCrc(..), Warning::HeaderFound(51)]
+    ));
+    assert_eq!(info.header_offset, 51);
+    let files = list_files_with_options(&data, &options).unwrap();
+    assert_eq!(files[0].extract_contents_vec().unwrap(), TEST_TXT_CONTENTS);
+
+    // The packed stream's size points past the end
+    let mut data = UNCOMPRESSED_TEST_ARCHIVE.to_vec();
+    data[57] = 0x7F;
+    assert!(sevenz::read::archive_info(&data).is_err());
+    let info = archive_info_with_options(&data, &options).unwrap();
+    assert!(matches!(
+        info.warnings[..],
+        [Warning::HeaderCrc(..), Warning::MissingFolderData(0)]
+    ));
+    let files = list_files_with_options(&data, &options).unwrap();
+    assert!(files[0].extract_contents_vec().is_err());
+
+    // Without a header, there's nothing to recover
+    let data = &UNCOMPRESSED_TEST_ARCHIVE[..50];
+    assert!(archive_info_with_options(data, &options).is_err());
+}
+
+#[test]
+fn truncated_archive_is_an_error() {
+    for len in 0..UNCOMPRESSED_TEST_ARCHIVE.len() {
+        assert!(sevenz::read::list_files(&UNCOMPRESSED_TEST_ARCHIVE[..len]).is_err());
+    }
+}
+
+#[test]
+fn huge_stream_count_is_an_error() {
+    // Claims billions of streams in the folder, without giving their sizes
+    let data = include_bytes!("../fuzz/corpus/parse/crash-huge-stream-count.7z");
+    assert!(sevenz::read::list_files(data).is_err());
+    let options = sevenz::read::ReadOptions {
+        recover: true,
+        ..Default::default()
+    };
+    assert!(sevenz::read::list_files_with_options(data, &options).is_err());
+}
+
+#[test]
+fn limits() {
+    use sevenz::read::{list_files_with_options, Error, Limit, Limits, ReadOptions};
+
+    fn options(f: impl Fn(&mut Limits)) -> ReadOptions {
+        let mut options = ReadOptions::default();
+        f(&mut options.limits);
+        options
+    }
+    fn parse_error(archive: &[u8], options: &ReadOptions) -> Option<Limit> {
+        match list_files_with_options(archive, options) {
+            Err(Error::LimitExceeded(limit)) => Some(limit),
+            _ => None,
+        }
+    }
+    fn extract_error(archive: &[u8], options: &ReadOptions) -> Option<Limit> {
+        let files = list_files_with_options(archive, options).unwrap();
+        match files[0].extract_contents_vec() {
+            Err(Error::LimitExceeded(limit)) => Some(limit),
+            _ => None,
+        }
+    }
+
+    // Exactly at the limits is fine
+    let at_limits = options(|l| {
+        l.max_entries = 1;
+        l.max_header_size = 90;
+        l.max_total_unpacked_size = 15;
+        l.max_entry_size = 15;
+        l.max_folders = 1;
+        l.max_coders = 1;
+        l.max_dictionary_size = 4096;
+    });
+    let files = list_files_with_options(UNCOMPRESSED_TEST_ARCHIVE, &at_limits).unwrap();
+    assert_eq!(files[0].extract_contents_vec().unwrap(), TEST_TXT_CONTENTS);
+
+    let archive = UNCOMPRESSED_TEST_ARCHIVE;
+    assert_eq!(
+        parse_error(archive, &options(|l| l.max_entries = 0)),
+        Some(Limit::Entries)
+    );
+    assert_eq!(
+        parse_error(archive, &options(|l| l.max_header_size = 89)),
+        Some(Limit::HeaderSize)
+    );
+    assert_eq!(
+        parse_error(archive, &options(|l| l.max_total_unpacked_size = 14)),
+        Some(Limit::TotalUnpackedSize)
+    );
+    assert_eq!(
+        parse_error(archive, &options(|l| l.max_folders = 0)),
+        Some(Limit::Folders)
+    );
+    assert_eq!(
+        parse_error(archive, &options(|l| l.max_coders = 0)),
+        Some(Limit::Coders)
+    );
+    assert_eq!(
+        extract_error(archive, &options(|l| l.max_entry_size = 14)),
+        Some(Limit::EntrySize)
+    );
+    assert_eq!(
+        extract_error(archive, &options(|l| l.max_dictionary_size = 4095)),
+        Some(Limit::DictionarySize)
+    );
+
+    // The comments are stored in an additional stream
+    assert_eq!(
+        parse_error(
+            COMMENTS_TEST_ARCHIVE,
+            &options(|l| l.max_decoded_header_size = 1)
+        ),
+        Some(Limit::DecodedHeaderSize)
+    );
+}
@@ -0,0 +1,41 @@
+use sevenz::read::{verify, CodecError, EntryStatus, Error, FolderError};
+
+const UNCOMPRESSED_TEST_ARCHIVE: &[u8] = include_bytes!("../testdata/test-uncompressed.txt.7z");
+
+#[test]
+fn verify_intact_archives() {
+    for entry in std::fs::read_dir("testdata").unwrap() {
+        let path = entry.unwrap().path();
+        if path.extension().is_some_and(|e| e == "7z") {
+            let report = verify(&std::fs::read(&path).unwrap()).unwrap();
+            assert!(report.is_ok(), "{}: {:?}", path.display(), report);
+        }
+    }
+
+    let report = verify(UNCOMPRESSED_TEST_ARCHIVE).unwrap();
+    assert_eq!(report.entries.len(), 1);
+    assert_eq!(report.entries[0].name, "test.txt");
+    assert_eq!(report.entries[0].status, EntryStatus::Ok);
+}
+
+#[test]
+fn verify_damaged_contents() {
+    let mut data = UNCOMPRESSED_TEST_ARCHIVE.to_vec();
+    data[40] ^= 0x01;
+    let report = verify(&data).unwrap();
+    assert!(!report.is_ok());
+    assert!(matches!(
+        report.entries[0].status,
+        EntryStatus::Failed(Error::Crc(..))
+    ));
+}
+
+#[test]
+fn verify_undecodable_folder() {
+    let mut data = UNCOMPRESSED_TEST_ARCHIVE.to_vec();
+    data[32] = 0x00;
+    let report = verify(&data).unwrap();
+    let error = Error::CodecFailed(CodecError::InvalidData);
+    assert_eq!(report.entries[0].status, EntryStatus::Failed(error.clone()));
+    assert_eq!(report.folders, vec![FolderError { index: 0, error }]);
+}
@@ -0,0 +1,145 @@
+use sevenz::read::{
+    list_files, list_files_with_registry, Attributes, Codec, CodecError, CodecFactory,
+    CodecRegistry, Encoder, EntryKind, FileTime, Method,
+};
+use sevenz::write::{ArchiveWriter, NewEntry};
+use std::sync::Arc;
+
+fn entries() -> Vec<NewEntry> {
+    vec![
+        NewEntry::directory("dir"),
+        NewEntry::file("dir/a.txt", b"duplicate\n".to_vec())
+            .with_modified(FileTime::new(132_708_606_310_000_000)),
+        NewEntry::file("b.txt", b"unique\n".to_vec()),
+        NewEntry::file("empty.txt", vec![]),
+        NewEntry::file("c.txt", b"duplicate\n".to_vec()).with_attributes(Attributes::new(0x21)),
+    ]
+}
+
+#[test]
+fn write_and_read_back() {
+    for solid in [true, false] {
+        let mut writer = ArchiveWriter::new();
+        writer.solid(solid);
+        entries().into_iter().for_each(|e| {
+            writer.add(e);
+        });
+        let archive = writer.finish().unwrap();
+
+        let files = list_files(&archive).unwrap();
+        let names: Vec<String> = files.iter().map(|f| f.name()).collect();
+        assert_eq!(names, ["dir", "dir/a.txt", "b.txt", "empty.txt", "c.txt"]);
+        let kinds: Vec<EntryKind> = files.iter().map(|f| f.kind()).collect();
+        assert_eq!(
+            kinds,
+            [
+                EntryKind::Directory,
+                EntryKind::File,
+                EntryKind::File,
+                EntryKind::EmptyFile,
+                EntryKind::File
+            ]
+        );
+        assert_eq!(files[1].extract_contents_vec().unwrap(), b"duplicate\n");
+        assert_eq!(files[2].extract_contents_vec().unwrap(), b"unique\n");
+        assert_eq!(files[4].extract_contents_vec().unwrap(), b"duplicate\n");
+        assert_eq!(
+            files[1].modified(),
+            Some(FileTime::new(132_708_606_310_000_000))
+        );
+        assert_eq!(files[2].modified(), None);
+        assert!(files[0].attributes().unwrap().is_directory());
+        assert!(files[4].attributes().unwrap().is_read_only());
+    }
+}
+
+#[test]
+fn write_empty_archive() {
+    let archive = ArchiveWriter::new().finish().unwrap();
+    assert!(list_files(&archive).unwrap().is_empty());
+}
+
+#[test]
+fn write_comments() {
+    let mut writer = ArchiveWriter::new();
+    writer
+        .comment("Archive comment")
+        .add(NewEntry::file("a.txt", b"a".to_vec()).with_comment("Comment on a"))
+        .add(NewEntry::file("b.txt", b"b".to_vec()));
+    let archive = writer.finish().unwrap();
+
+    let files = list_files(&archive).unwrap();
+    assert_eq!(files[0].comment().as_deref(), Some("Comment on a"));
+    assert_eq!(files[1].comment(), None);
+    assert_eq!(
+        sevenz::read::archive_comment(&archive).unwrap().as_deref(),
+        Some("Archive comment")
+    );
+}
+
+/// Flips every bit, standing in for a private method.
+struct Invert {}
+
+impl Codec for Invert {
+    fn decode(&self, data: &[u8]) -> Result<Vec<u8>, CodecError> {
+        Ok(data.iter().map(|b| !b).collect())
+    }
+}
+
+impl Encoder for Invert {
+    fn encode(&self, data: &[u8]) -> Result<Vec<u8>, CodecError> {
+        Ok(data.iter().map(|b| !b).collect())
+    }
+
+    fn properties(&self) -> Option<Vec<u8>> {
+        Some(vec![0x2A])
+    }
+}
+
+impl CodecFactory for Invert {
+    fn decoder(&self, props: Option<&[u8]>) -> Result<Box<dyn Codec>, CodecError> {
+        assert_eq!(props, Some(&[0x2A][..]));
+        Ok(Box::new(Invert {}))
+    }
+
+    fn encoder(&self) -> Option<Box<dyn Encoder>> {
+        Some(Box::new(Invert {}))
+    }
+}
+
+#[test]
+fn write_with_registered_method() {
+    let mut registry = CodecRegistry::new();
+    registry.register(&[0x7F, 0x01], Invert {});
+    let registry = Arc::new(registry);
+
+    for solid in [true, false] {
+        let mut writer = ArchiveWriter::new();
+        writer
+            .solid(solid)
+            .method(&[0x7F, 0x01])
+            .registry(registry.clone());
+        entries().into_iter().for_each(|e| {
+            writer.add(e);
+        });
+        let archive = writer.finish().unwrap();
+        // The contents are stored inverted
+        let inverted: Vec<u8> = b"unique\n".iter().map(|b| !b).collect();
+        assert!(archive.windows(inverted.len()).any(|w| w == inverted));
+
+        let files = list_files_with_registry(&archive, registry.clone()).unwrap();
+        assert_eq!(files[2].extract_contents_vec().unwrap(), b"unique\n");
+        assert_eq!(files[4].extract_contents_vec().unwrap(), b"duplicate\n");
+        assert!(list_files(&archive).unwrap()[2]
+            .extract_contents_vec()
+            .is_err());
+    }
+
+    // LZMA2 can only be decoded
+    let mut writer = ArchiveWriter::new();
+    writer.method(&[0x21]);
+    assert!(matches!(
+        writer.finish(),
+        Err(CodecError::UnsupportedMethod(Method::Lzma2(None)))
+    ));
+}